@@ -0,0 +1,177 @@
+use soroban_sdk::{symbol_short, Address, BytesN, Env, String};
+
+use crate::types::{
+    DisputeOutcome, HookKind, MatchingPool, PresaveTip, TipEscrow, TipRecord, TrackRelease, Voucher,
+};
+
+pub fn escrow_created(env: &Env, escrow: &TipEscrow) {
+    env.events().publish(
+        (symbol_short!("escrow"), symbol_short!("created")),
+        escrow.clone(),
+    );
+}
+
+pub fn escrow_released(env: &Env, escrow_id: String, artist: Address, amount: i128) {
+    env.events().publish(
+        (symbol_short!("escrow"), symbol_short!("released")),
+        (escrow_id, artist, amount),
+    );
+}
+
+pub fn escrow_refunded(env: &Env, escrow_id: String, tipper: Address, amount: i128) {
+    env.events().publish(
+        (symbol_short!("escrow"), symbol_short!("refunded")),
+        (escrow_id, tipper, amount),
+    );
+}
+
+pub fn escrow_expired(env: &Env, escrow_id: String, tipper: Address, amount: i128) {
+    env.events().publish(
+        (symbol_short!("escrow"), symbol_short!("expired")),
+        (escrow_id, tipper, amount),
+    );
+}
+
+pub fn dispute_raised(env: &Env, escrow_id: String, raised_by: Address, deadline: u64) {
+    env.events().publish(
+        (symbol_short!("dispute"), symbol_short!("raised")),
+        (escrow_id, raised_by, deadline),
+    );
+}
+
+pub fn dispute_resolved(
+    env: &Env,
+    escrow_id: String,
+    arbiter: Address,
+    tipper_amount: i128,
+    artist_amount: i128,
+) {
+    env.events().publish(
+        (symbol_short!("dispute"), symbol_short!("resolved")),
+        (escrow_id, arbiter, tipper_amount, artist_amount),
+    );
+}
+
+pub fn dispute_defaulted(env: &Env, escrow_id: String, outcome: DisputeOutcome, amount: i128) {
+    env.events().publish(
+        (symbol_short!("dispute"), symbol_short!("default")),
+        (escrow_id, outcome, amount),
+    );
+}
+
+pub fn arbiter_assigned(env: &Env, escrow_id: String, arbiter: Address) {
+    env.events().publish(
+        (symbol_short!("dispute"), symbol_short!("arbiter")),
+        (escrow_id, arbiter),
+    );
+}
+
+pub fn tip_sent(env: &Env, tip: &TipRecord) {
+    env.events()
+        .publish((symbol_short!("tip"), symbol_short!("sent")), tip.clone());
+}
+
+pub fn balance_credited(env: &Env, recipient: &Address, token: &Address, amount: i128) {
+    env.events().publish(
+        (symbol_short!("claim"), symbol_short!("credited")),
+        (recipient.clone(), token.clone(), amount),
+    );
+}
+
+pub fn balance_claimed(env: &Env, recipient: Address, token: Address, amount: i128) {
+    env.events().publish(
+        (symbol_short!("claim"), symbol_short!("claimed")),
+        (recipient, token, amount),
+    );
+}
+
+pub fn tip_matched(env: &Env, pool_id: u64, tip_id: u64, artist: &Address, matched: i128) {
+    env.events().publish(
+        (symbol_short!("pool"), symbol_short!("matched")),
+        (pool_id, tip_id, artist.clone(), matched),
+    );
+}
+
+pub fn pool_created(env: &Env, pool: &MatchingPool) {
+    env.events().publish(
+        (symbol_short!("pool"), symbol_short!("created")),
+        pool.clone(),
+    );
+}
+
+pub fn pool_accepted(env: &Env, pool_id: u64, artist: Address) {
+    env.events().publish(
+        (symbol_short!("pool"), symbol_short!("accepted")),
+        (pool_id, artist),
+    );
+}
+
+pub fn pool_reclaimed(env: &Env, pool_id: u64, sponsor: Address, amount: i128) {
+    env.events().publish(
+        (symbol_short!("pool"), symbol_short!("reclaimed")),
+        (pool_id, sponsor, amount),
+    );
+}
+
+pub fn release_scheduled(env: &Env, release: &TrackRelease) {
+    env.events().publish(
+        (symbol_short!("release"), symbol_short!("scheduled")),
+        release.clone(),
+    );
+}
+
+pub fn release_cancelled(env: &Env, track_id: String, artist: Address) {
+    env.events().publish(
+        (symbol_short!("release"), symbol_short!("cancelled")),
+        (track_id, artist),
+    );
+}
+
+pub fn presave_locked(env: &Env, presave: &PresaveTip) {
+    env.events().publish(
+        (symbol_short!("presave"), symbol_short!("locked")),
+        presave.clone(),
+    );
+}
+
+pub fn presave_claimed(env: &Env, presave_id: u64, tip_id: u64, artist: Address) {
+    env.events().publish(
+        (symbol_short!("presave"), symbol_short!("claimed")),
+        (presave_id, tip_id, artist),
+    );
+}
+
+pub fn presave_refunded(env: &Env, presave_id: u64, sender: Address, amount: i128) {
+    env.events().publish(
+        (symbol_short!("presave"), symbol_short!("refunded")),
+        (presave_id, sender, amount),
+    );
+}
+
+pub fn voucher_created(env: &Env, voucher: &Voucher) {
+    env.events().publish(
+        (symbol_short!("voucher"), symbol_short!("created")),
+        voucher.clone(),
+    );
+}
+
+pub fn voucher_redeemed(env: &Env, code_hash: BytesN<32>, tip_id: u64, artist: Address) {
+    env.events().publish(
+        (symbol_short!("voucher"), symbol_short!("redeemed")),
+        (code_hash, tip_id, artist),
+    );
+}
+
+pub fn voucher_reclaimed(env: &Env, code_hash: BytesN<32>, buyer: Address, amount: i128) {
+    env.events().publish(
+        (symbol_short!("voucher"), symbol_short!("reclaimed")),
+        (code_hash, buyer, amount),
+    );
+}
+
+pub fn hook_failed(env: &Env, tip_id: u64, kind: HookKind, contract: Address) {
+    env.events().publish(
+        (symbol_short!("hook"), symbol_short!("failed")),
+        (tip_id, kind, contract),
+    );
+}
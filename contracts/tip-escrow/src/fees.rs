@@ -0,0 +1,56 @@
+use soroban_sdk::{Address, Env};
+
+use crate::storage;
+use crate::types::{Error, FeeConfig};
+
+const MAX_FEE_PERCENTAGE: u32 = 10000;
+
+pub fn validate_config(config: &FeeConfig) -> Result<(), Error> {
+    if config.fee_percentage > MAX_FEE_PERCENTAGE {
+        return Err(Error::InvalidFeeConfig);
+    }
+    Ok(())
+}
+
+pub fn validate_bounds(minimum_fee: i128, maximum_fee: i128) -> Result<(), Error> {
+    if minimum_fee < 0 || maximum_fee < minimum_fee {
+        return Err(Error::InvalidFeeConfig);
+    }
+    Ok(())
+}
+
+/// Platform fee owed on a tip, and who collects it.
+/// Follows the backend fee calculator: waiver first, then the asset's minimum floor and
+/// maximum cap, and never more than the tip itself.
+pub fn calculate_fee(
+    env: &Env,
+    artist: &Address,
+    token: &Address,
+    amount: i128,
+) -> Option<(i128, Address)> {
+    let config = storage::get_fee_config(env)?;
+
+    if config.waived_for_verified_artists && storage::is_fee_waived(env, artist) {
+        return None;
+    }
+
+    let mut fee = (amount * config.fee_percentage as i128) / 10000;
+    // A delisted asset whose funds the contract still holds has no bounds left
+    if let Some(asset) = storage::get_asset_config(env, token) {
+        if fee > 0 && fee < asset.minimum_fee {
+            fee = asset.minimum_fee;
+        }
+        if fee > asset.maximum_fee {
+            fee = asset.maximum_fee;
+        }
+    }
+    if fee > amount {
+        fee = amount;
+    }
+
+    if fee > 0 {
+        Some((fee, config.fee_collector))
+    } else {
+        None
+    }
+}
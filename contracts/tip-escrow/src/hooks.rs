@@ -0,0 +1,55 @@
+use soroban_sdk::{Env, IntoVal, InvokeError, Symbol, Val, Vec};
+
+use crate::types::{Error, HookFailureMode, HookKind, TipHook, TipRecord};
+use crate::{events, storage};
+
+/// Notify the registered badge and reward contracts of a recorded tip.
+/// Runs inside the tip's invocation, so a failing hook in `Revert` mode undoes the tip.
+pub fn run_hooks(env: &Env, tip: &TipRecord) -> Result<(), Error> {
+    if let Some(hook) = storage::get_hook(env, HookKind::Badge) {
+        // Escrow tips carry no genre, so they never count towards genre badges
+        let args = (tip.sender.clone(), tip.amount, false).into_val(env);
+        invoke(env, tip.tip_id, HookKind::Badge, &hook, "record_tip", args)?;
+    }
+
+    if let Some(hook) = storage::get_hook(env, HookKind::Reward) {
+        let rate = storage::get_reward_rate(env, &tip.token);
+        let reward = (tip.amount * rate as i128) / 10000;
+        if reward > 0 {
+            let args = (tip.sender.clone(), reward).into_val(env);
+            invoke(
+                env,
+                tip.tip_id,
+                HookKind::Reward,
+                &hook,
+                "mint_reward",
+                args,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn invoke(
+    env: &Env,
+    tip_id: u64,
+    kind: HookKind,
+    hook: &TipHook,
+    func: &str,
+    args: Vec<Val>,
+) -> Result<(), Error> {
+    let result =
+        env.try_invoke_contract::<Val, InvokeError>(&hook.contract, &Symbol::new(env, func), args);
+    if let Ok(Ok(_)) = result {
+        return Ok(());
+    }
+
+    match hook.on_failure {
+        HookFailureMode::Revert => Err(Error::HookFailed),
+        HookFailureMode::Skip => {
+            events::hook_failed(env, tip_id, kind, hook.contract.clone());
+            Ok(())
+        }
+    }
+}
@@ -1,25 +1,221 @@
 #![no_std]
 
+mod events;
+mod fees;
+mod hooks;
+mod matching;
 mod storage;
+mod tips;
 mod types;
 
 #[cfg(test)]
 mod test;
 
-use soroban_sdk::{contract, contractimpl, symbol_short, token, Address, Env, String, Vec};
-use types::{Asset, Error, RoyaltySplit, TipEscrow, TipRecord};
+use soroban_sdk::{
+    contract, contractimpl, symbol_short, token, xdr::ToXdr, Address, Bytes, BytesN, Env, String,
+    Vec,
+};
+use types::{
+    Asset, AssetConfig, DisputeConfig, DisputeOutcome, Error, EscrowStatus, FeeConfig,
+    HookFailureMode, HookKind, MatchingPool, MatchingTerms, MessageHash, PayoutMode, PresaveStatus,
+    PresaveTip, ReleaseStatus, RoyaltySplit, SplitConfig, SupporterStats, TipContext, TipEscrow,
+    TipHook, TipIndex, TipMetadata, TipRecord, TipStats, TrackRelease, Voucher, VoucherStatus,
+};
+
+const DEFAULT_RESOLUTION_WINDOW: u64 = 604_800; // 7 days
+const MAX_PAGE_SIZE: u32 = 50;
+const MAX_BATCH_SIZE: u32 = 25;
 
 #[contract]
 pub struct TipEscrowContract;
 
 #[contractimpl]
 impl TipEscrowContract {
+    /// Set the contract admin. The admin configures disputes and acts as the fallback arbiter.
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        if storage::has_admin(&env) {
+            return Err(Error::AlreadyInitialized);
+        }
+        storage::set_admin(&env, &admin);
+        Ok(())
+    }
+
+    /// Configure the dispute resolution window and the outcome applied when it lapses
+    pub fn set_dispute_config(
+        env: Env,
+        resolution_window: u64,
+        default_outcome: DisputeOutcome,
+    ) -> Result<(), Error> {
+        require_admin(&env)?;
+
+        if resolution_window == 0 {
+            return Err(Error::InvalidExpiry);
+        }
+
+        storage::set_dispute_config(
+            &env,
+            &DisputeConfig {
+                resolution_window,
+                default_outcome,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn get_dispute_config(env: Env) -> DisputeConfig {
+        dispute_config(&env)
+    }
+
+    /// Configure the platform fee taken from every `send_tip`
+    pub fn set_fee_config(env: Env, config: FeeConfig) -> Result<(), Error> {
+        require_admin(&env)?;
+        fees::validate_config(&config)?;
+        storage::set_fee_config(&env, &config);
+        Ok(())
+    }
+
+    pub fn get_fee_config(env: Env) -> Option<FeeConfig> {
+        storage::get_fee_config(&env)
+    }
+
+    /// Add or remove an artist from the fee waiver list.
+    /// Waivers only apply while `waived_for_verified_artists` is enabled.
+    pub fn set_fee_waiver(env: Env, artist: Address, waived: bool) -> Result<(), Error> {
+        require_admin(&env)?;
+        storage::set_fee_waiver(&env, &artist, waived);
+        Ok(())
+    }
+
+    pub fn is_fee_waived(env: Env, artist: Address) -> bool {
+        storage::is_fee_waived(&env, &artist)
+    }
+
+    /// Register the Stellar Asset Contract address used for native XLM.
+    /// Escrows already funded keep settling through the contract they were created with.
+    pub fn set_native_asset(env: Env, token_address: Address) -> Result<(), Error> {
+        require_admin(&env)?;
+        storage::set_native_asset(&env, &token_address);
+        Ok(())
+    }
+
+    pub fn get_native_asset(env: Env) -> Option<Address> {
+        storage::get_native_asset(&env)
+    }
+
+    /// Allowlist an asset for tips and escrows, or update its tip limits.
+    /// A newly listed asset has no fee floor or cap until `set_asset_fee_bounds` is called.
+    pub fn allow_asset(
+        env: Env,
+        token_address: Address,
+        min_amount: i128,
+        max_amount: i128,
+    ) -> Result<(), Error> {
+        require_admin(&env)?;
+
+        if min_amount <= 0 || max_amount < min_amount {
+            return Err(Error::InvalidAmount);
+        }
+
+        let (minimum_fee, maximum_fee) = storage::get_asset_config(&env, &token_address)
+            .map_or((0, i128::MAX), |config| {
+                (config.minimum_fee, config.maximum_fee)
+            });
+        storage::set_asset_config(
+            &env,
+            &token_address,
+            &AssetConfig {
+                min_amount,
+                max_amount,
+                minimum_fee,
+                maximum_fee,
+            },
+        );
+        Ok(())
+    }
+
+    /// Set the platform fee floor and cap for an allowlisted asset, in its smallest unit
+    pub fn set_asset_fee_bounds(
+        env: Env,
+        token_address: Address,
+        minimum_fee: i128,
+        maximum_fee: i128,
+    ) -> Result<(), Error> {
+        require_admin(&env)?;
+        fees::validate_bounds(minimum_fee, maximum_fee)?;
+
+        let mut config =
+            storage::get_asset_config(&env, &token_address).ok_or(Error::AssetNotAllowed)?;
+        config.minimum_fee = minimum_fee;
+        config.maximum_fee = maximum_fee;
+        storage::set_asset_config(&env, &token_address, &config);
+        Ok(())
+    }
+
+    /// Stop accepting new tips in an asset. Existing escrows can still settle.
+    pub fn remove_asset(env: Env, token_address: Address) -> Result<(), Error> {
+        require_admin(&env)?;
+        storage::remove_asset_config(&env, &token_address);
+        Ok(())
+    }
+
+    pub fn get_asset_config(env: Env, token_address: Address) -> Option<AssetConfig> {
+        storage::get_asset_config(&env, &token_address)
+    }
+
+    pub fn get_allowed_assets(env: Env) -> Vec<Address> {
+        storage::get_allowed_assets(&env)
+    }
+
+    /// Register the contract notified after every tip, replacing any earlier one of the same kind.
+    /// Reward hooks mint through `mint_reward`, so this contract must be the reward token's admin.
+    pub fn set_hook(
+        env: Env,
+        kind: HookKind,
+        contract: Address,
+        on_failure: HookFailureMode,
+    ) -> Result<(), Error> {
+        require_admin(&env)?;
+        storage::set_hook(
+            &env,
+            kind,
+            &TipHook {
+                contract,
+                on_failure,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn remove_hook(env: Env, kind: HookKind) -> Result<(), Error> {
+        require_admin(&env)?;
+        storage::remove_hook(&env, kind);
+        Ok(())
+    }
+
+    pub fn get_hook(env: Env, kind: HookKind) -> Option<TipHook> {
+        storage::get_hook(&env, kind)
+    }
+
+    /// Reward tokens minted to the sender per 10,000 units tipped in an asset.
+    /// A rate of zero turns rewards off for that asset.
+    pub fn set_reward_rate(env: Env, token_address: Address, rate: u32) -> Result<(), Error> {
+        require_admin(&env)?;
+        storage::set_reward_rate(&env, &token_address, rate);
+        Ok(())
+    }
+
+    pub fn get_reward_rate(env: Env, token_address: Address) -> u32 {
+        storage::get_reward_rate(&env, &token_address)
+    }
+
     pub fn create_escrow(
         env: Env,
         tipper: Address,
         artist: Address,
         amount: i128,
         asset: Asset,
+        expires_at: u64,
+        arbiter: Option<Address>,
     ) -> Result<String, Error> {
         tipper.require_auth();
 
@@ -27,14 +223,23 @@ impl TipEscrowContract {
             return Err(Error::InvalidAmount);
         }
 
-        // Lock funds inside contract
-        match &asset {
-            Asset::Token(token_address) => {
-                let token_client = token::Client::new(&env, token_address);
-                token_client.transfer(&tipper, &env.current_contract_address(), &amount);
-            }
+        let now = env.ledger().timestamp();
+        if expires_at <= now {
+            return Err(Error::InvalidExpiry);
+        }
+        if let Some(arbiter) = &arbiter {
+            check_arbiter(arbiter, &tipper, &artist)?;
         }
 
+        // Native XLM is pinned to the contract registered now, so the escrow settles in
+        // the token it was funded with even if the admin later registers another one
+        let token_address = resolve_asset(&env, &asset)?;
+        tips::check_asset_allowed(&env, &token_address, amount)?;
+
+        // Lock funds inside contract
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.transfer(&tipper, &env.current_contract_address(), &amount);
+
         let mut counter: u32 = env
             .storage()
             .instance()
@@ -59,7 +264,7 @@ impl TipEscrowContract {
             }
         }
 
-        let escrow_id = String::from_slice(&env, &buf[i..]);
+        let escrow_id = String::from_bytes(&env, &buf[i..]);
 
         let escrow = TipEscrow {
             escrow_id: escrow_id.clone(),
@@ -67,15 +272,18 @@ impl TipEscrowContract {
             artist,
             amount,
             asset,
+            token: token_address,
+            status: EscrowStatus::Pending,
+            created_at: now,
+            expires_at,
+            arbiter,
+            disputed_by: None,
+            dispute_deadline: None,
         };
 
         storage::save_escrow(&env, escrow_id.clone(), &escrow);
 
-        // Emit event
-        env.events().publish(
-            (symbol_short!("escrow"), symbol_short!("created")),
-            escrow.clone(),
-        );
+        events::escrow_created(&env, &escrow);
 
         Ok(escrow_id)
     }
@@ -84,71 +292,860 @@ impl TipEscrowContract {
         storage::get_escrow(&env, escrow_id).ok_or(Error::EscrowNotFound)
     }
 
-    /// Send a tip to an artist with optional royalty distribution
+    /// Release a pending escrow to the artist. Only the tipper can approve the payout.
+    pub fn release_escrow(env: Env, escrow_id: String) -> Result<(), Error> {
+        let mut escrow = load_pending_escrow(&env, &escrow_id)?;
+        escrow.tipper.require_auth();
+
+        escrow.status = EscrowStatus::Released;
+        storage::save_escrow(&env, escrow_id.clone(), &escrow);
+
+        transfer_from_escrow(&env, &escrow, &escrow.artist, escrow.amount);
+
+        events::escrow_released(&env, escrow_id, escrow.artist, escrow.amount);
+
+        Ok(())
+    }
+
+    /// Return a pending escrow to the tipper. Only the artist can decline a tip early.
+    pub fn refund_escrow(env: Env, escrow_id: String) -> Result<(), Error> {
+        let mut escrow = load_pending_escrow(&env, &escrow_id)?;
+        escrow.artist.require_auth();
+
+        escrow.status = EscrowStatus::Refunded;
+        storage::save_escrow(&env, escrow_id.clone(), &escrow);
+
+        transfer_from_escrow(&env, &escrow, &escrow.tipper, escrow.amount);
+
+        events::escrow_refunded(&env, escrow_id, escrow.tipper, escrow.amount);
+
+        Ok(())
+    }
+
+    /// Let the tipper reclaim a pending escrow once it has passed its expiry
+    pub fn reclaim_expired_escrow(env: Env, escrow_id: String) -> Result<(), Error> {
+        let mut escrow = load_pending_escrow(&env, &escrow_id)?;
+        escrow.tipper.require_auth();
+
+        if env.ledger().timestamp() < escrow.expires_at {
+            return Err(Error::EscrowNotExpired);
+        }
+
+        escrow.status = EscrowStatus::Expired;
+        storage::save_escrow(&env, escrow_id.clone(), &escrow);
+
+        transfer_from_escrow(&env, &escrow, &escrow.tipper, escrow.amount);
+
+        events::escrow_expired(&env, escrow_id, escrow.tipper, escrow.amount);
+
+        Ok(())
+    }
+
+    /// Assign or replace the arbiter of an unsettled escrow
+    pub fn assign_arbiter(env: Env, escrow_id: String, arbiter: Address) -> Result<(), Error> {
+        require_admin(&env)?;
+
+        let mut escrow =
+            storage::get_escrow(&env, escrow_id.clone()).ok_or(Error::EscrowNotFound)?;
+        if escrow.status != EscrowStatus::Pending && escrow.status != EscrowStatus::Disputed {
+            return Err(Error::EscrowNotPending);
+        }
+        check_arbiter(&arbiter, &escrow.tipper, &escrow.artist)?;
+
+        escrow.arbiter = Some(arbiter.clone());
+        storage::save_escrow(&env, escrow_id.clone(), &escrow);
+
+        events::arbiter_assigned(&env, escrow_id, arbiter);
+
+        Ok(())
+    }
+
+    /// Freeze a pending escrow until the arbiter resolves it. Either party may raise a dispute.
+    pub fn raise_dispute(env: Env, escrow_id: String, caller: Address) -> Result<u64, Error> {
+        let mut escrow = load_pending_escrow(&env, &escrow_id)?;
+
+        if caller != escrow.tipper && caller != escrow.artist {
+            return Err(Error::Unauthorized);
+        }
+        caller.require_auth();
+
+        let now = env.ledger().timestamp();
+        if now >= escrow.expires_at {
+            return Err(Error::EscrowExpired);
+        }
+
+        let deadline = now + dispute_config(&env).resolution_window;
+        escrow.status = EscrowStatus::Disputed;
+        escrow.disputed_by = Some(caller.clone());
+        escrow.dispute_deadline = Some(deadline);
+        storage::save_escrow(&env, escrow_id.clone(), &escrow);
+
+        events::dispute_raised(&env, escrow_id, caller, deadline);
+
+        Ok(deadline)
+    }
+
+    /// Split a disputed escrow between tipper and artist. The artist receives the remainder.
+    pub fn resolve_dispute(env: Env, escrow_id: String, tipper_amount: i128) -> Result<(), Error> {
+        let mut escrow = load_disputed_escrow(&env, &escrow_id)?;
+
+        let arbiter = match escrow.arbiter.clone() {
+            Some(arbiter) => arbiter,
+            None => storage::get_admin(&env).ok_or(Error::NotInitialized)?,
+        };
+        arbiter.require_auth();
+
+        if env.ledger().timestamp() > escrow.dispute_deadline.unwrap_or(0) {
+            return Err(Error::DisputeDeadlinePassed);
+        }
+
+        if tipper_amount < 0 || tipper_amount > escrow.amount {
+            return Err(Error::InvalidAmount);
+        }
+        let artist_amount = escrow.amount - tipper_amount;
+
+        escrow.status = EscrowStatus::Resolved;
+        storage::save_escrow(&env, escrow_id.clone(), &escrow);
+
+        if tipper_amount > 0 {
+            transfer_from_escrow(&env, &escrow, &escrow.tipper, tipper_amount);
+        }
+        if artist_amount > 0 {
+            transfer_from_escrow(&env, &escrow, &escrow.artist, artist_amount);
+        }
+
+        events::dispute_resolved(&env, escrow_id, arbiter, tipper_amount, artist_amount);
+
+        Ok(())
+    }
+
+    /// Apply the default outcome to a dispute whose resolution deadline has passed.
+    /// Anyone may call this so funds never stay locked behind an absent arbiter.
+    pub fn settle_expired_dispute(env: Env, escrow_id: String) -> Result<(), Error> {
+        let mut escrow = load_disputed_escrow(&env, &escrow_id)?;
+
+        if env.ledger().timestamp() <= escrow.dispute_deadline.unwrap_or(0) {
+            return Err(Error::DisputeDeadlineNotPassed);
+        }
+
+        let outcome = dispute_config(&env).default_outcome;
+        let (status, recipient) = match outcome {
+            DisputeOutcome::RefundTipper => (EscrowStatus::Refunded, escrow.tipper.clone()),
+            DisputeOutcome::ReleaseToArtist => (EscrowStatus::Released, escrow.artist.clone()),
+        };
+
+        escrow.status = status;
+        storage::save_escrow(&env, escrow_id.clone(), &escrow);
+
+        transfer_from_escrow(&env, &escrow, &recipient, escrow.amount);
+
+        events::dispute_defaulted(&env, escrow_id, outcome, escrow.amount);
+
+        Ok(())
+    }
+
+    /// Send a tip to an artist with optional royalty distribution.
+    /// Repeating a call with the same `idempotency_key` returns the original tip id
+    /// without moving funds again, so clients can safely retry submissions.
     pub fn send_tip(
         env: Env,
         sender: Address,
         artist: Address,
         token_address: Address,
         amount: i128,
-    ) -> u64 {
+        idempotency_key: Option<BytesN<32>>,
+        metadata: Option<TipMetadata>,
+    ) -> Result<u64, Error> {
         sender.require_auth();
 
-        let token_client = token::Client::new(&env, &token_address);
-        let tip_id = env.ledger().sequence() as u64;
-
-        // Check if artist has royalty splits configured
-        if let Some(splits) = storage::get_splits(&env, &artist) {
-            // Distribute according to splits
-            let mut remaining = amount;
-
-            for split in splits.iter() {
-                let split_amount = (amount * split.percentage as i128) / 10000;
-                if split_amount > 0 {
-                    token_client.transfer(&sender, &split.recipient, &split_amount);
-                    remaining -= split_amount;
-                }
+        if let Some(key) = &idempotency_key {
+            if let Some(tip_id) = storage::get_idempotent_tip(&env, &sender, key) {
+                return Ok(tip_id);
             }
+        }
 
-            // Send remaining to artist
-            if remaining > 0 {
-                token_client.transfer(&sender, &artist, &remaining);
-            }
-        } else {
-            // No splits, send full amount to artist
-            token_client.transfer(&sender, &artist, &amount);
+        let tip_id = tips::process_tip(
+            &env,
+            &sender,
+            &sender,
+            &artist,
+            &token_address,
+            amount,
+            metadata,
+        )?;
+
+        if let Some(key) = &idempotency_key {
+            storage::save_idempotent_tip(&env, &sender, key, tip_id);
+        }
+
+        Ok(tip_id)
+    }
+
+    /// Tip several artists in one call, e.g. everyone on a playlist.
+    /// The batch is all-or-nothing: if any tip fails, none of them are recorded or paid.
+    /// `metadata` is attached to every tip in the batch.
+    /// Returns the tip ids in the same order as `tips`.
+    pub fn send_tips(
+        env: Env,
+        sender: Address,
+        token_address: Address,
+        tips: Vec<(Address, i128)>,
+        metadata: Option<TipMetadata>,
+    ) -> Result<Vec<u64>, Error> {
+        sender.require_auth();
+
+        if tips.is_empty() || tips.len() > MAX_BATCH_SIZE {
+            return Err(Error::InvalidBatchSize);
+        }
+
+        let mut tip_ids = Vec::new(&env);
+        for (artist, amount) in tips.iter() {
+            let tip_id = tips::process_tip(
+                &env,
+                &sender,
+                &sender,
+                &artist,
+                &token_address,
+                amount,
+                metadata.clone(),
+            )?;
+            tip_ids.push_back(tip_id);
+        }
+
+        Ok(tip_ids)
+    }
+
+    /// Choose whether split recipients are paid directly or accrue claimable balances
+    pub fn set_payout_mode(env: Env, artist: Address, mode: PayoutMode) {
+        artist.require_auth();
+        storage::set_payout_mode(&env, &artist, mode);
+    }
+
+    pub fn get_payout_mode(env: Env, artist: Address) -> PayoutMode {
+        storage::get_payout_mode(&env, &artist)
+    }
+
+    /// Withdraw everything a recipient has accrued in one token
+    pub fn claim(env: Env, recipient: Address, token_address: Address) -> Result<i128, Error> {
+        recipient.require_auth();
+
+        let amount = storage::take_claimable(&env, &recipient, &token_address);
+        if amount == 0 {
+            return Err(Error::NothingToClaim);
         }
 
-        // Record tip
-        let tip = TipRecord {
-            sender: sender.clone(),
-            artist: artist.clone(),
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.transfer(&env.current_contract_address(), &recipient, &amount);
+
+        events::balance_claimed(&env, recipient, token_address, amount);
+
+        Ok(amount)
+    }
+
+    pub fn get_claimable(env: Env, recipient: Address, token_address: Address) -> i128 {
+        storage::get_claimable(&env, &recipient, &token_address)
+    }
+
+    /// All outstanding balances of a recipient as (token, amount) pairs
+    pub fn get_claimable_balances(env: Env, recipient: Address) -> Vec<(Address, i128)> {
+        let mut balances = Vec::new(&env);
+        for token_address in storage::get_claimable_tokens(&env, &recipient).iter() {
+            let amount = storage::get_claimable(&env, &recipient, &token_address);
+            balances.push_back((token_address, amount));
+        }
+        balances
+    }
+
+    /// Sum of unclaimed balances the contract owes in one token
+    pub fn get_total_claimable(env: Env, token_address: Address) -> i128 {
+        storage::get_total_claimable(&env, &token_address)
+    }
+
+    /// Schedule a track release that fans can pre-save tip. Track ids are scoped
+    /// to the artist, so another artist cannot claim the same id.
+    /// Tips stay locked until `release_at` and are refundable if the release is
+    /// cancelled or moved past `deadline`.
+    pub fn schedule_release(
+        env: Env,
+        artist: Address,
+        track_id: String,
+        release_at: u64,
+        deadline: u64,
+    ) -> Result<(), Error> {
+        artist.require_auth();
+
+        if storage::get_release(&env, &artist, &track_id).is_some() {
+            return Err(Error::ReleaseAlreadyScheduled);
+        }
+        if release_at <= env.ledger().timestamp() || deadline < release_at {
+            return Err(Error::InvalidReleaseSchedule);
+        }
+
+        let release = TrackRelease {
+            track_id,
+            artist,
+            release_at,
+            deadline,
+            status: ReleaseStatus::Scheduled,
+        };
+        storage::save_release(&env, &release);
+
+        events::release_scheduled(&env, &release);
+
+        Ok(())
+    }
+
+    /// Move an upcoming release to a new date. The deadline does not move with it.
+    pub fn reschedule_release(
+        env: Env,
+        artist: Address,
+        track_id: String,
+        release_at: u64,
+    ) -> Result<(), Error> {
+        artist.require_auth();
+        let mut release = load_upcoming_release(&env, &artist, &track_id)?;
+
+        if release_at <= env.ledger().timestamp() {
+            return Err(Error::InvalidReleaseSchedule);
+        }
+
+        release.release_at = release_at;
+        storage::save_release(&env, &release);
+
+        events::release_scheduled(&env, &release);
+
+        Ok(())
+    }
+
+    /// Call off an upcoming release, making every locked pre-save tip refundable
+    pub fn cancel_release(env: Env, artist: Address, track_id: String) -> Result<(), Error> {
+        artist.require_auth();
+        let mut release = load_upcoming_release(&env, &artist, &track_id)?;
+
+        release.status = ReleaseStatus::Cancelled;
+        storage::save_release(&env, &release);
+
+        events::release_cancelled(&env, track_id, release.artist);
+
+        Ok(())
+    }
+
+    pub fn get_release(env: Env, artist: Address, track_id: String) -> Result<TrackRelease, Error> {
+        storage::get_release(&env, &artist, &track_id).ok_or(Error::ReleaseNotFound)
+    }
+
+    /// Lock a tip against an upcoming release
+    pub fn send_presave_tip(
+        env: Env,
+        sender: Address,
+        artist: Address,
+        track_id: String,
+        token_address: Address,
+        amount: i128,
+    ) -> Result<u64, Error> {
+        sender.require_auth();
+
+        if sender == artist {
+            return Err(Error::SelfTip);
+        }
+
+        let release = load_upcoming_release(&env, &artist, &track_id)?;
+        if release.release_at > release.deadline {
+            return Err(Error::InvalidReleaseSchedule);
+        }
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        tips::check_asset_allowed(&env, &token_address, amount)?;
+
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.transfer(&sender, &env.current_contract_address(), &amount);
+
+        let presave = PresaveTip {
+            presave_id: storage::next_presave_id(&env),
+            sender,
+            artist: release.artist,
+            track_id,
+            token: token_address,
+            amount,
+            status: PresaveStatus::Locked,
+            created_at: env.ledger().timestamp(),
+            tip_id: None,
+        };
+        storage::save_presave_tip(&env, &presave);
+
+        events::presave_locked(&env, &presave);
+
+        Ok(presave.presave_id)
+    }
+
+    /// Turn a locked pre-save tip into a regular tip once its track is out.
+    /// Fees, splits and matching apply as if the fan had tipped at release.
+    pub fn claim_presave_tip(env: Env, presave_id: u64) -> Result<u64, Error> {
+        let mut presave = load_locked_presave(&env, presave_id)?;
+        presave.artist.require_auth();
+
+        let release = storage::get_release(&env, &presave.artist, &presave.track_id)
+            .ok_or(Error::ReleaseNotFound)?;
+        let released = release.status == ReleaseStatus::Scheduled
+            && release.release_at <= release.deadline
+            && env.ledger().timestamp() >= release.release_at;
+        if !released {
+            return Err(Error::PresaveNotClaimable);
+        }
+
+        let tip_id = tips::process_tip(
+            &env,
+            &env.current_contract_address(),
+            &presave.sender,
+            &presave.artist,
+            &presave.token,
+            presave.amount,
+            Some(TipMetadata {
+                track_id: Some(presave.track_id.clone()),
+                context: TipContext::Track,
+                message_hash: MessageHash::None,
+            }),
+        )?;
+
+        presave.status = PresaveStatus::Claimed;
+        presave.tip_id = Some(tip_id);
+        storage::save_presave_tip(&env, &presave);
+
+        events::presave_claimed(&env, presave_id, tip_id, presave.artist);
+
+        Ok(tip_id)
+    }
+
+    /// Return a locked pre-save tip to the fan after a cancellation or a slip past the deadline
+    pub fn refund_presave_tip(env: Env, presave_id: u64) -> Result<(), Error> {
+        let mut presave = load_locked_presave(&env, presave_id)?;
+        presave.sender.require_auth();
+
+        let release = storage::get_release(&env, &presave.artist, &presave.track_id)
+            .ok_or(Error::ReleaseNotFound)?;
+        let refundable =
+            release.status == ReleaseStatus::Cancelled || release.release_at > release.deadline;
+        if !refundable {
+            return Err(Error::PresaveNotRefundable);
+        }
+
+        presave.status = PresaveStatus::Refunded;
+        storage::save_presave_tip(&env, &presave);
+
+        let token_client = token::Client::new(&env, &presave.token);
+        token_client.transfer(
+            &env.current_contract_address(),
+            &presave.sender,
+            &presave.amount,
+        );
+
+        events::presave_refunded(&env, presave_id, presave.sender, presave.amount);
+
+        Ok(())
+    }
+
+    pub fn get_presave_tip(env: Env, presave_id: u64) -> Result<PresaveTip, Error> {
+        storage::get_presave_tip(&env, presave_id).ok_or(Error::PresaveTipNotFound)
+    }
+
+    /// Fund a gift-card voucher. Whoever knows the code whose SHA-256 is `code_hash`
+    /// can spend it on a tip to any artist until `expires_at`.
+    pub fn create_voucher(
+        env: Env,
+        buyer: Address,
+        token_address: Address,
+        amount: i128,
+        code_hash: BytesN<32>,
+        expires_at: u64,
+    ) -> Result<(), Error> {
+        buyer.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if expires_at <= env.ledger().timestamp() {
+            return Err(Error::InvalidExpiry);
+        }
+        if storage::get_voucher(&env, &code_hash).is_some() {
+            return Err(Error::VoucherExists);
+        }
+        tips::check_asset_allowed(&env, &token_address, amount)?;
+
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.transfer(&buyer, &env.current_contract_address(), &amount);
+
+        let voucher = Voucher {
+            code_hash,
+            buyer,
+            token: token_address,
             amount,
-            timestamp: env.ledger().timestamp(),
+            expires_at,
+            status: VoucherStatus::Active,
+            tip_id: None,
+        };
+        storage::save_voucher(&env, &voucher);
+
+        events::voucher_created(&env, &voucher);
+
+        Ok(())
+    }
+
+    /// First step of spending a voucher. `commitment` is the SHA-256 of
+    /// `(code, redeemer, artist)` in XDR, so it reveals nothing about the code.
+    pub fn commit_voucher_redemption(env: Env, redeemer: Address, commitment: BytesN<32>) {
+        redeemer.require_auth();
+
+        storage::save_redemption_commit(&env, &redeemer, &commitment);
+    }
+
+    /// Spend a voucher on a tip by revealing its code. The redeemer must have committed
+    /// to the code and artist in an earlier ledger, so a code copied from a pending
+    /// redemption cannot be used to front-run it. The tip is credited to `redeemer`
+    /// and goes through the normal fee, split and matching logic.
+    pub fn redeem_voucher(
+        env: Env,
+        redeemer: Address,
+        code: Bytes,
+        artist: Address,
+        metadata: Option<TipMetadata>,
+    ) -> Result<u64, Error> {
+        redeemer.require_auth();
+
+        let code_hash: BytesN<32> = env.crypto().sha256(&code).into();
+        let mut voucher = load_active_voucher(&env, &code_hash)?;
+
+        if env.ledger().timestamp() >= voucher.expires_at {
+            return Err(Error::VoucherExpired);
+        }
+
+        let claim = (code, redeemer.clone(), artist.clone());
+        let commitment: BytesN<32> = env.crypto().sha256(&claim.to_xdr(&env)).into();
+        match storage::get_redemption_commit(&env, &redeemer, &commitment) {
+            Some(committed_at) if committed_at < env.ledger().sequence() => {}
+            _ => return Err(Error::RedemptionNotCommitted),
+        }
+        storage::remove_redemption_commit(&env, &redeemer, &commitment);
+
+        let tip_id = tips::process_tip(
+            &env,
+            &env.current_contract_address(),
+            &redeemer,
+            &artist,
+            &voucher.token,
+            voucher.amount,
+            metadata,
+        )?;
+
+        voucher.status = VoucherStatus::Redeemed;
+        voucher.tip_id = Some(tip_id);
+        storage::save_voucher(&env, &voucher);
+
+        events::voucher_redeemed(&env, code_hash, tip_id, artist);
+
+        Ok(tip_id)
+    }
+
+    /// Return an unredeemed voucher's funds to its buyer after expiry
+    pub fn reclaim_voucher(env: Env, code_hash: BytesN<32>) -> Result<(), Error> {
+        let mut voucher = load_active_voucher(&env, &code_hash)?;
+        voucher.buyer.require_auth();
+
+        if env.ledger().timestamp() < voucher.expires_at {
+            return Err(Error::VoucherNotExpired);
+        }
+
+        voucher.status = VoucherStatus::Reclaimed;
+        storage::save_voucher(&env, &voucher);
+
+        let token_client = token::Client::new(&env, &voucher.token);
+        token_client.transfer(
+            &env.current_contract_address(),
+            &voucher.buyer,
+            &voucher.amount,
+        );
+
+        events::voucher_reclaimed(&env, code_hash, voucher.buyer, voucher.amount);
+
+        Ok(())
+    }
+
+    pub fn get_voucher(env: Env, code_hash: BytesN<32>) -> Result<Voucher, Error> {
+        storage::get_voucher(&env, &code_hash).ok_or(Error::VoucherNotFound)
+    }
+
+    /// Lock a sponsor budget that matches fan tips to the listed artists until `ends_at`.
+    /// Each listed artist has to accept the pool before their tips get matched.
+    pub fn create_matching_pool(
+        env: Env,
+        sponsor: Address,
+        token_address: Address,
+        budget: i128,
+        terms: MatchingTerms,
+    ) -> Result<u64, Error> {
+        sponsor.require_auth();
+
+        matching::validate_terms(&env, budget, &terms)?;
+        storage::get_asset_config(&env, &token_address).ok_or(Error::AssetNotAllowed)?;
+
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.transfer(&sponsor, &env.current_contract_address(), &budget);
+
+        let pool = MatchingPool {
+            pool_id: storage::next_pool_id(&env),
+            sponsor,
+            token: token_address,
+            terms,
+            budget,
+            remaining_budget: budget,
         };
-        storage::save_tip(&env, tip_id, &tip);
+        storage::save_pool(&env, &pool);
 
-        tip_id
+        events::pool_created(&env, &pool);
+
+        Ok(pool.pool_id)
     }
 
-    /// Configure royalty splits for an artist
-    pub fn set_royalty_splits(env: Env, artist: Address, splits: Vec<RoyaltySplit>) {
+    /// Opt an artist into a pool that lists them, so it starts matching their tips
+    pub fn accept_matching_pool(env: Env, artist: Address, pool_id: u64) -> Result<(), Error> {
         artist.require_auth();
 
-        // Validate splits total <= 100%
-        let total: u32 = splits.iter().map(|s| s.percentage).sum();
-        assert!(total <= 10000, "Total splits exceed 100%");
+        let pool = storage::get_pool(&env, pool_id).ok_or(Error::PoolNotFound)?;
+        matching::attach_pool(&env, &artist, &pool)?;
+
+        events::pool_accepted(&env, pool_id, artist);
+
+        Ok(())
+    }
+
+    /// Return the unspent budget of an ended pool to its sponsor
+    pub fn reclaim_matching_pool(env: Env, pool_id: u64) -> Result<i128, Error> {
+        let mut pool = storage::get_pool(&env, pool_id).ok_or(Error::PoolNotFound)?;
+        pool.sponsor.require_auth();
+
+        if env.ledger().timestamp() < pool.terms.ends_at {
+            return Err(Error::PoolStillActive);
+        }
+        if pool.remaining_budget == 0 {
+            return Err(Error::PoolEmpty);
+        }
+
+        let amount = pool.remaining_budget;
+        pool.remaining_budget = 0;
+        storage::save_pool(&env, &pool);
 
-        storage::save_splits(&env, &artist, &splits);
+        let token_client = token::Client::new(&env, &pool.token);
+        token_client.transfer(&env.current_contract_address(), &pool.sponsor, &amount);
+
+        events::pool_reclaimed(&env, pool_id, pool.sponsor, amount);
+
+        Ok(amount)
+    }
+
+    pub fn get_matching_pool(env: Env, pool_id: u64) -> Result<MatchingPool, Error> {
+        storage::get_pool(&env, pool_id).ok_or(Error::PoolNotFound)
+    }
+
+    /// Ids of the pools an artist has accepted
+    pub fn get_artist_matching_pools(env: Env, artist: Address) -> Vec<u64> {
+        storage::get_artist_pools(&env, &artist)
+    }
+
+    /// Configure royalty splits for an artist. Each call creates a new version,
+    /// effective immediately, and returns its version number.
+    pub fn set_royalty_splits(
+        env: Env,
+        artist: Address,
+        splits: Vec<RoyaltySplit>,
+    ) -> Result<u32, Error> {
+        artist.require_auth();
+
+        tips::validate_splits(&artist, &splits)?;
+
+        Ok(storage::save_splits(&env, &artist, &splits))
     }
 
     /// Get royalty splits for an artist
     pub fn get_royalty_splits(env: Env, artist: Address) -> Option<Vec<RoyaltySplit>> {
-        storage::get_splits(&env, &artist)
+        storage::get_current_splits(&env, &artist).map(|config| config.splits)
+    }
+
+    /// Current split version for an artist, 0 if splits were never configured
+    pub fn get_split_version(env: Env, artist: Address) -> u32 {
+        storage::get_split_version(&env, &artist)
+    }
+
+    /// Look up a past split configuration, e.g. the one referenced by `TipRecord::split_version`
+    pub fn get_split_config(env: Env, artist: Address, version: u32) -> Result<SplitConfig, Error> {
+        storage::get_split_config(&env, &artist, version).ok_or(Error::SplitVersionNotFound)
+    }
+
+    /// Tip totals for an artist across all assets
+    pub fn get_artist_stats(env: Env, artist: Address) -> TipStats {
+        storage::get_artist_stats(&env, &artist)
+    }
+
+    /// Tip totals for an artist in a single asset
+    pub fn get_artist_asset_stats(env: Env, artist: Address, token_address: Address) -> TipStats {
+        storage::get_artist_asset_stats(&env, &artist, &token_address)
+    }
+
+    /// Lifetime totals of a fan's tips to an artist
+    pub fn get_supporter_stats(env: Env, artist: Address, tipper: Address) -> SupporterStats {
+        storage::get_supporter_stats(&env, &artist, &tipper)
+    }
+
+    /// Lifetime amount a fan has tipped an artist in a single asset
+    pub fn get_supporter_asset_total(
+        env: Env,
+        artist: Address,
+        tipper: Address,
+        token_address: Address,
+    ) -> i128 {
+        storage::get_supporter_asset_total(&env, &artist, &tipper, &token_address)
+    }
+
+    pub fn get_tip(env: Env, tip_id: u64) -> Result<TipRecord, Error> {
+        storage::get_tip(&env, tip_id).ok_or(Error::TipNotFound)
+    }
+
+    /// Page through all tips in the order they were recorded.
+    /// `cursor` is the position to start from; at most `MAX_PAGE_SIZE` tips are returned.
+    pub fn get_tips(env: Env, cursor: u32, limit: u32) -> Vec<TipRecord> {
+        storage::get_index_page(&env, &TipIndex::All, cursor, limit.min(MAX_PAGE_SIZE))
+    }
+
+    /// Page through the tips received by an artist, oldest first
+    pub fn get_tips_by_artist(
+        env: Env,
+        artist: Address,
+        cursor: u32,
+        limit: u32,
+    ) -> Vec<TipRecord> {
+        storage::get_index_page(
+            &env,
+            &TipIndex::Artist(artist),
+            cursor,
+            limit.min(MAX_PAGE_SIZE),
+        )
+    }
+
+    /// Page through the tips sent by a fan, oldest first
+    pub fn get_tips_by_sender(
+        env: Env,
+        sender: Address,
+        cursor: u32,
+        limit: u32,
+    ) -> Vec<TipRecord> {
+        storage::get_index_page(
+            &env,
+            &TipIndex::Sender(sender),
+            cursor,
+            limit.min(MAX_PAGE_SIZE),
+        )
+    }
+
+    /// Page through the tips that referenced one of an artist's tracks, oldest first
+    pub fn get_tips_by_track(
+        env: Env,
+        artist: Address,
+        track_id: String,
+        cursor: u32,
+        limit: u32,
+    ) -> Vec<TipRecord> {
+        storage::get_index_page(
+            &env,
+            &TipIndex::Track(artist, track_id),
+            cursor,
+            limit.min(MAX_PAGE_SIZE),
+        )
+    }
+
+    /// Number of entries in a tip index, so callers know where the next sync cursor is
+    pub fn get_tip_count(env: Env, index: TipIndex) -> u32 {
+        storage::get_index_len(&env, &index)
     }
+}
 
-    /// Get all tips
-    pub fn get_tips(env: Env) -> Vec<TipRecord> {
-        storage::get_tips(&env)
+fn load_pending_escrow(env: &Env, escrow_id: &String) -> Result<TipEscrow, Error> {
+    let escrow = storage::get_escrow(env, escrow_id.clone()).ok_or(Error::EscrowNotFound)?;
+    if escrow.status != EscrowStatus::Pending {
+        return Err(Error::EscrowNotPending);
     }
+    Ok(escrow)
+}
+
+fn load_disputed_escrow(env: &Env, escrow_id: &String) -> Result<TipEscrow, Error> {
+    let escrow = storage::get_escrow(env, escrow_id.clone()).ok_or(Error::EscrowNotFound)?;
+    if escrow.status != EscrowStatus::Disputed {
+        return Err(Error::EscrowNotDisputed);
+    }
+    Ok(escrow)
+}
+
+/// A scheduled release whose date has not arrived yet
+fn load_upcoming_release(
+    env: &Env,
+    artist: &Address,
+    track_id: &String,
+) -> Result<TrackRelease, Error> {
+    let release = storage::get_release(env, artist, track_id).ok_or(Error::ReleaseNotFound)?;
+    if release.status != ReleaseStatus::Scheduled {
+        return Err(Error::ReleaseNotScheduled);
+    }
+    if env.ledger().timestamp() >= release.release_at {
+        return Err(Error::ReleaseAlreadyOut);
+    }
+    Ok(release)
+}
+
+fn load_locked_presave(env: &Env, presave_id: u64) -> Result<PresaveTip, Error> {
+    let presave = storage::get_presave_tip(env, presave_id).ok_or(Error::PresaveTipNotFound)?;
+    if presave.status != PresaveStatus::Locked {
+        return Err(Error::PresaveTipNotLocked);
+    }
+    Ok(presave)
+}
+
+fn load_active_voucher(env: &Env, code_hash: &BytesN<32>) -> Result<Voucher, Error> {
+    let voucher = storage::get_voucher(env, code_hash).ok_or(Error::VoucherNotFound)?;
+    if voucher.status != VoucherStatus::Active {
+        return Err(Error::VoucherNotActive);
+    }
+    Ok(voucher)
+}
+
+/// An arbiter must be neutral: neither party can rule on their own dispute
+fn check_arbiter(arbiter: &Address, tipper: &Address, artist: &Address) -> Result<(), Error> {
+    if arbiter == tipper || arbiter == artist {
+        return Err(Error::InvalidArbiter);
+    }
+    Ok(())
+}
+
+fn require_admin(env: &Env) -> Result<Address, Error> {
+    let admin = storage::get_admin(env).ok_or(Error::NotInitialized)?;
+    admin.require_auth();
+    Ok(admin)
+}
+
+fn dispute_config(env: &Env) -> DisputeConfig {
+    storage::get_dispute_config(env).unwrap_or(DisputeConfig {
+        resolution_window: DEFAULT_RESOLUTION_WINDOW,
+        default_outcome: DisputeOutcome::RefundTipper,
+    })
+}
+
+fn resolve_asset(env: &Env, asset: &Asset) -> Result<Address, Error> {
+    match asset {
+        Asset::Token(token_address) => Ok(token_address.clone()),
+        Asset::Native => storage::get_native_asset(env).ok_or(Error::NativeAssetNotSet),
+    }
+}
+
+/// Pay out of an escrow's locked funds, in the token it was funded with
+fn transfer_from_escrow(env: &Env, escrow: &TipEscrow, to: &Address, amount: i128) {
+    let token_client = token::Client::new(env, &escrow.token);
+    token_client.transfer(&env.current_contract_address(), to, &amount);
 }
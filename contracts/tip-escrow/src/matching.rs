@@ -0,0 +1,103 @@
+use soroban_sdk::{Address, Env, Vec};
+
+use crate::types::{Error, MatchingPool, MatchingTerms};
+use crate::{events, storage, tips};
+
+const MAX_POOL_ARTISTS: u32 = 20;
+const MAX_POOLS_PER_ARTIST: u32 = 5;
+
+pub fn validate_terms(env: &Env, budget: i128, terms: &MatchingTerms) -> Result<(), Error> {
+    if budget <= 0
+        || terms.match_ratio == 0
+        || terms.per_tip_cap <= 0
+        || terms.ends_at <= env.ledger().timestamp()
+        || terms.artists.is_empty()
+        || terms.artists.len() > MAX_POOL_ARTISTS
+    {
+        return Err(Error::InvalidMatchingPool);
+    }
+
+    for (i, artist) in terms.artists.iter().enumerate() {
+        if terms
+            .artists
+            .iter()
+            .skip(i + 1)
+            .any(|other| other == artist)
+        {
+            return Err(Error::InvalidMatchingPool);
+        }
+    }
+    Ok(())
+}
+
+/// Attach a pool to an artist who accepted it, dropping pools that can no longer match.
+/// Pools only count against an artist's limit once the artist has opted in, so a
+/// sponsor cannot fill the slots of artists who never asked for their pools.
+pub fn attach_pool(env: &Env, artist: &Address, pool: &MatchingPool) -> Result<(), Error> {
+    if !pool.terms.artists.contains(artist) || !is_active(env, pool) {
+        return Err(Error::InvalidMatchingPool);
+    }
+
+    let mut pool_ids = Vec::new(env);
+    for pool_id in storage::get_artist_pools(env, artist).iter() {
+        if pool_id == pool.pool_id {
+            return Ok(());
+        }
+        if storage::get_pool(env, pool_id).is_some_and(|p| is_active(env, &p)) {
+            pool_ids.push_back(pool_id);
+        }
+    }
+
+    if pool_ids.len() >= MAX_POOLS_PER_ARTIST {
+        return Err(Error::TooManyPools);
+    }
+
+    pool_ids.push_back(pool.pool_id);
+    storage::set_artist_pools(env, artist, &pool_ids);
+    Ok(())
+}
+
+pub fn is_active(env: &Env, pool: &MatchingPool) -> bool {
+    pool.remaining_budget > 0 && env.ledger().timestamp() < pool.terms.ends_at
+}
+
+/// Match a fan tip from every active pool backing the artist in the same token.
+/// Matched funds go out from the contract through the artist's normal split logic.
+/// Returns the total matched amount.
+pub fn apply_matches(
+    env: &Env,
+    tip_id: u64,
+    artist: &Address,
+    token_address: &Address,
+    amount: i128,
+) -> i128 {
+    let contract = env.current_contract_address();
+    let mut total_matched = 0;
+
+    for pool_id in storage::get_artist_pools(env, artist).iter() {
+        let mut pool = match storage::get_pool(env, pool_id) {
+            Some(pool) => pool,
+            None => continue,
+        };
+        if pool.token != *token_address || !is_active(env, &pool) {
+            continue;
+        }
+
+        let matched = ((amount * pool.terms.match_ratio as i128) / 10000)
+            .min(pool.terms.per_tip_cap)
+            .min(pool.remaining_budget);
+        if matched <= 0 {
+            continue;
+        }
+
+        pool.remaining_budget -= matched;
+        storage::save_pool(env, &pool);
+
+        tips::distribute(env, token_address, &contract, artist, matched);
+        events::tip_matched(env, pool_id, tip_id, artist, matched);
+
+        total_matched += matched;
+    }
+
+    total_matched
+}
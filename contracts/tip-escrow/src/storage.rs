@@ -1,32 +1,169 @@
-use soroban_sdk::{contracttype, symbol_short, Address, Env, String, Symbol, Vec};
+use soroban_sdk::{contracttype, Address, BytesN, Env, IntoVal, String, TryFromVal, Val, Vec};
 
-use crate::types::{RoyaltySplit, TipEscrow, TipRecord};
+use crate::types::{
+    AssetConfig, DisputeConfig, FeeConfig, HookKind, MatchingPool, PayoutMode, PresaveTip,
+    RoyaltySplit, SplitConfig, SupporterStats, TipEscrow, TipHook, TipIndex, TipRecord, TipStats,
+    TrackRelease, Voucher,
+};
 
-const TIPS: Symbol = symbol_short!("TIPS");
-const SPLITS: Symbol = symbol_short!("SPLITS");
+const LIFETIME_THRESHOLD: u32 = 100_000; // ~6 days at 5s/ledger
+const EXTEND_TO: u32 = 200_000;
 
 #[contracttype]
 #[derive(Clone)]
 pub enum DataKey {
     Escrow(String),
+    Admin,
+    DisputeConfig,
+    Tip(u64),
+    TipIndexLen(TipIndex),
+    TipIndexEntry(TipIndex, u32), // index + position -> tip id
+    TipCounter,
+    Idempotency(Address, BytesN<32>), // sender + client key -> tip id
+    FeeConfig,
+    FeeWaiver(Address),
+    NativeAsset,
+    AllowedAssets,
+    AssetConfig(Address),
+    PayoutMode(Address),
+    Claimable(Address, Address), // recipient + token -> balance
+    ClaimableTokens(Address),
+    TotalClaimable(Address),
+    SplitVersion(Address),
+    SplitConfig(Address, u32), // artist + version
+    ArtistStats(Address),
+    ArtistAssetStats(Address, Address), // artist + token
+    SupporterStats(Address, Address),   // artist + tipper
+    SupporterAssetTotal(Address, Address, Address), // artist + tipper + token
+    PoolCounter,
+    MatchingPool(u64),
+    ArtistPools(Address),
+    Release(Address, String), // artist + track_id
+    PresaveCounter,
+    PresaveTip(u64),
+    Voucher(BytesN<32>),
+    RedemptionCommit(Address, BytesN<32>), // redeemer + commitment -> ledger sequence
+    Hook(HookKind),
+    RewardRate(Address),
 }
 
-pub fn save_tip(env: &Env, _tip_id: u64, tip: &TipRecord) {
-    let mut tips: Vec<TipRecord> = env.storage().instance().get(&TIPS).unwrap_or(Vec::new(env));
-    tips.push_back(tip.clone());
-    env.storage().instance().set(&TIPS, &tips);
+fn write_persistent<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    env.storage().persistent().set(key, value);
+    env.storage()
+        .persistent()
+        .extend_ttl(key, LIFETIME_THRESHOLD, EXTEND_TO);
+}
+
+fn read_persistent<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> Option<V> {
+    let value = env.storage().persistent().get(key);
+    if value.is_some() {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, LIFETIME_THRESHOLD, EXTEND_TO);
+    }
+    value
+}
+
+/// Store a tip under its own entry and append it to the global, artist and sender indexes,
+/// plus the track index when the tip references a track
+pub fn save_tip(env: &Env, tip: &TipRecord) {
+    write_persistent(env, &DataKey::Tip(tip.tip_id), tip);
+
+    append_to_index(env, TipIndex::All, tip.tip_id);
+    append_to_index(env, TipIndex::Artist(tip.artist.clone()), tip.tip_id);
+    append_to_index(env, TipIndex::Sender(tip.sender.clone()), tip.tip_id);
+
+    if let Some(track_id) = tip.metadata.track_id.clone() {
+        append_to_index(
+            env,
+            TipIndex::Track(tip.artist.clone(), track_id),
+            tip.tip_id,
+        );
+    }
+}
+
+/// Allocate the next tip id. Ids start at 1 and never repeat.
+pub fn next_tip_id(env: &Env) -> u64 {
+    let id: u64 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TipCounter)
+        .unwrap_or(0)
+        + 1;
+    env.storage().instance().set(&DataKey::TipCounter, &id);
+    id
+}
+
+pub fn get_idempotent_tip(env: &Env, sender: &Address, key: &BytesN<32>) -> Option<u64> {
+    read_persistent(env, &DataKey::Idempotency(sender.clone(), key.clone()))
+}
+
+pub fn save_idempotent_tip(env: &Env, sender: &Address, key: &BytesN<32>, tip_id: u64) {
+    write_persistent(
+        env,
+        &DataKey::Idempotency(sender.clone(), key.clone()),
+        &tip_id,
+    );
 }
 
-pub fn get_tips(env: &Env) -> Vec<TipRecord> {
-    env.storage().instance().get(&TIPS).unwrap_or(Vec::new(env))
+pub fn get_tip(env: &Env, tip_id: u64) -> Option<TipRecord> {
+    read_persistent(env, &DataKey::Tip(tip_id))
 }
 
-pub fn save_splits(env: &Env, artist: &Address, splits: &Vec<RoyaltySplit>) {
-    env.storage().instance().set(&(SPLITS, artist), splits);
+fn append_to_index(env: &Env, index: TipIndex, tip_id: u64) {
+    let len = get_index_len(env, &index);
+    write_persistent(env, &DataKey::TipIndexEntry(index.clone(), len), &tip_id);
+    write_persistent(env, &DataKey::TipIndexLen(index), &(len + 1));
 }
 
-pub fn get_splits(env: &Env, artist: &Address) -> Option<Vec<RoyaltySplit>> {
-    env.storage().instance().get(&(SPLITS, artist))
+pub fn get_index_len(env: &Env, index: &TipIndex) -> u32 {
+    read_persistent(env, &DataKey::TipIndexLen(index.clone())).unwrap_or(0)
+}
+
+/// Read up to `limit` tips of an index, oldest first, starting at position `cursor`
+pub fn get_index_page(env: &Env, index: &TipIndex, cursor: u32, limit: u32) -> Vec<TipRecord> {
+    let mut tips = Vec::new(env);
+    let end = cursor.saturating_add(limit).min(get_index_len(env, index));
+
+    for position in cursor..end {
+        let tip_id: Option<u64> =
+            read_persistent(env, &DataKey::TipIndexEntry(index.clone(), position));
+        if let Some(tip) = tip_id.and_then(|id| get_tip(env, id)) {
+            tips.push_back(tip);
+        }
+    }
+
+    tips
+}
+
+/// Store a new split configuration for an artist and return its version (starting at 1).
+/// Earlier versions are kept so past tips can be audited against the splits they used.
+pub fn save_splits(env: &Env, artist: &Address, splits: &Vec<RoyaltySplit>) -> u32 {
+    let version = get_split_version(env, artist) + 1;
+    let config = SplitConfig {
+        version,
+        splits: splits.clone(),
+        effective_from: env.ledger().timestamp(),
+    };
+    write_persistent(env, &DataKey::SplitConfig(artist.clone(), version), &config);
+    write_persistent(env, &DataKey::SplitVersion(artist.clone()), &version);
+    version
+}
+
+pub fn get_split_version(env: &Env, artist: &Address) -> u32 {
+    read_persistent(env, &DataKey::SplitVersion(artist.clone())).unwrap_or(0)
+}
+
+pub fn get_split_config(env: &Env, artist: &Address, version: u32) -> Option<SplitConfig> {
+    read_persistent(env, &DataKey::SplitConfig(artist.clone(), version))
+}
+
+/// The split configuration currently in force for an artist, if any
+pub fn get_current_splits(env: &Env, artist: &Address) -> Option<SplitConfig> {
+    match get_split_version(env, artist) {
+        0 => None,
+        version => get_split_config(env, artist, version),
+    }
 }
 
 pub fn save_escrow(env: &Env, escrow_id: String, escrow: &TipEscrow) {
@@ -38,3 +175,359 @@ pub fn save_escrow(env: &Env, escrow_id: String, escrow: &TipEscrow) {
 pub fn get_escrow(env: &Env, escrow_id: String) -> Option<TipEscrow> {
     env.storage().persistent().get(&DataKey::Escrow(escrow_id))
 }
+
+pub fn has_admin(env: &Env) -> bool {
+    env.storage().instance().has(&DataKey::Admin)
+}
+
+pub fn set_admin(env: &Env, admin: &Address) {
+    env.storage().instance().set(&DataKey::Admin, admin);
+}
+
+pub fn get_admin(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&DataKey::Admin)
+}
+
+pub fn set_dispute_config(env: &Env, config: &DisputeConfig) {
+    env.storage()
+        .instance()
+        .set(&DataKey::DisputeConfig, config);
+}
+
+pub fn get_dispute_config(env: &Env) -> Option<DisputeConfig> {
+    env.storage().instance().get(&DataKey::DisputeConfig)
+}
+
+pub fn set_fee_config(env: &Env, config: &FeeConfig) {
+    env.storage().instance().set(&DataKey::FeeConfig, config);
+}
+
+pub fn get_fee_config(env: &Env) -> Option<FeeConfig> {
+    env.storage().instance().get(&DataKey::FeeConfig)
+}
+
+pub fn set_fee_waiver(env: &Env, artist: &Address, waived: bool) {
+    let key = DataKey::FeeWaiver(artist.clone());
+    if waived {
+        write_persistent(env, &key, &true);
+    } else {
+        env.storage().persistent().remove(&key);
+    }
+}
+
+pub fn is_fee_waived(env: &Env, artist: &Address) -> bool {
+    read_persistent(env, &DataKey::FeeWaiver(artist.clone())).unwrap_or(false)
+}
+
+pub fn set_native_asset(env: &Env, token: &Address) {
+    env.storage().instance().set(&DataKey::NativeAsset, token);
+}
+
+pub fn get_native_asset(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&DataKey::NativeAsset)
+}
+
+pub fn get_allowed_assets(env: &Env) -> Vec<Address> {
+    env.storage()
+        .instance()
+        .get(&DataKey::AllowedAssets)
+        .unwrap_or(Vec::new(env))
+}
+
+pub fn set_asset_config(env: &Env, token: &Address, config: &AssetConfig) {
+    let mut assets = get_allowed_assets(env);
+    if !assets.contains(token) {
+        assets.push_back(token.clone());
+        env.storage()
+            .instance()
+            .set(&DataKey::AllowedAssets, &assets);
+    }
+    write_persistent(env, &DataKey::AssetConfig(token.clone()), config);
+}
+
+pub fn remove_asset_config(env: &Env, token: &Address) {
+    let mut assets = get_allowed_assets(env);
+    if let Some(index) = assets.first_index_of(token) {
+        assets.remove(index);
+        env.storage()
+            .instance()
+            .set(&DataKey::AllowedAssets, &assets);
+    }
+    env.storage()
+        .persistent()
+        .remove(&DataKey::AssetConfig(token.clone()));
+}
+
+pub fn get_asset_config(env: &Env, token: &Address) -> Option<AssetConfig> {
+    read_persistent(env, &DataKey::AssetConfig(token.clone()))
+}
+
+pub fn set_payout_mode(env: &Env, artist: &Address, mode: PayoutMode) {
+    write_persistent(env, &DataKey::PayoutMode(artist.clone()), &mode);
+}
+
+pub fn get_payout_mode(env: &Env, artist: &Address) -> PayoutMode {
+    read_persistent(env, &DataKey::PayoutMode(artist.clone())).unwrap_or(PayoutMode::Push)
+}
+
+pub fn get_claimable(env: &Env, recipient: &Address, token: &Address) -> i128 {
+    read_persistent(env, &DataKey::Claimable(recipient.clone(), token.clone())).unwrap_or(0)
+}
+
+pub fn get_claimable_tokens(env: &Env, recipient: &Address) -> Vec<Address> {
+    read_persistent(env, &DataKey::ClaimableTokens(recipient.clone())).unwrap_or(Vec::new(env))
+}
+
+pub fn get_total_claimable(env: &Env, token: &Address) -> i128 {
+    read_persistent(env, &DataKey::TotalClaimable(token.clone())).unwrap_or(0)
+}
+
+pub fn add_claimable(env: &Env, recipient: &Address, token: &Address, amount: i128) {
+    let balance = get_claimable(env, recipient, token);
+    write_persistent(
+        env,
+        &DataKey::Claimable(recipient.clone(), token.clone()),
+        &(balance + amount),
+    );
+
+    if balance == 0 {
+        let mut tokens = get_claimable_tokens(env, recipient);
+        if !tokens.contains(token) {
+            tokens.push_back(token.clone());
+            write_persistent(env, &DataKey::ClaimableTokens(recipient.clone()), &tokens);
+        }
+    }
+
+    let total = get_total_claimable(env, token);
+    write_persistent(
+        env,
+        &DataKey::TotalClaimable(token.clone()),
+        &(total + amount),
+    );
+}
+
+/// Zero a recipient's balance in one token and return what it held
+pub fn take_claimable(env: &Env, recipient: &Address, token: &Address) -> i128 {
+    let balance = get_claimable(env, recipient, token);
+    if balance == 0 {
+        return 0;
+    }
+
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Claimable(recipient.clone(), token.clone()));
+
+    let mut tokens = get_claimable_tokens(env, recipient);
+    if let Some(index) = tokens.first_index_of(token) {
+        tokens.remove(index);
+        write_persistent(env, &DataKey::ClaimableTokens(recipient.clone()), &tokens);
+    }
+
+    let total = get_total_claimable(env, token);
+    write_persistent(
+        env,
+        &DataKey::TotalClaimable(token.clone()),
+        &(total - balance),
+    );
+
+    balance
+}
+
+pub fn get_artist_stats(env: &Env, artist: &Address) -> TipStats {
+    read_persistent(env, &DataKey::ArtistStats(artist.clone())).unwrap_or_default()
+}
+
+pub fn get_artist_asset_stats(env: &Env, artist: &Address, token: &Address) -> TipStats {
+    read_persistent(
+        env,
+        &DataKey::ArtistAssetStats(artist.clone(), token.clone()),
+    )
+    .unwrap_or_default()
+}
+
+pub fn get_supporter_stats(env: &Env, artist: &Address, tipper: &Address) -> SupporterStats {
+    read_persistent(
+        env,
+        &DataKey::SupporterStats(artist.clone(), tipper.clone()),
+    )
+    .unwrap_or_default()
+}
+
+pub fn get_supporter_asset_total(
+    env: &Env,
+    artist: &Address,
+    tipper: &Address,
+    token: &Address,
+) -> i128 {
+    read_persistent(
+        env,
+        &DataKey::SupporterAssetTotal(artist.clone(), tipper.clone(), token.clone()),
+    )
+    .unwrap_or(0)
+}
+
+/// Fold a recorded tip into the artist, artist-asset and supporter aggregates
+pub fn record_tip_stats(env: &Env, tip: &TipRecord) {
+    let supporter = get_supporter_stats(env, &tip.artist, &tip.sender);
+    let supporter_asset_total =
+        get_supporter_asset_total(env, &tip.artist, &tip.sender, &tip.token);
+
+    let mut artist_stats = get_artist_stats(env, &tip.artist);
+    add_to_stats(&mut artist_stats, tip, supporter.tip_count == 0);
+    write_persistent(
+        env,
+        &DataKey::ArtistStats(tip.artist.clone()),
+        &artist_stats,
+    );
+
+    let mut asset_stats = get_artist_asset_stats(env, &tip.artist, &tip.token);
+    add_to_stats(&mut asset_stats, tip, supporter_asset_total == 0);
+    write_persistent(
+        env,
+        &DataKey::ArtistAssetStats(tip.artist.clone(), tip.token.clone()),
+        &asset_stats,
+    );
+
+    let supporter = SupporterStats {
+        total_tipped: supporter.total_tipped + tip.amount,
+        tip_count: supporter.tip_count + 1,
+        first_tip_at: if supporter.tip_count == 0 {
+            tip.timestamp
+        } else {
+            supporter.first_tip_at
+        },
+        last_tip_at: tip.timestamp,
+    };
+    write_persistent(
+        env,
+        &DataKey::SupporterStats(tip.artist.clone(), tip.sender.clone()),
+        &supporter,
+    );
+    write_persistent(
+        env,
+        &DataKey::SupporterAssetTotal(tip.artist.clone(), tip.sender.clone(), tip.token.clone()),
+        &(supporter_asset_total + tip.amount),
+    );
+}
+
+fn add_to_stats(stats: &mut TipStats, tip: &TipRecord, new_tipper: bool) {
+    stats.total_received += tip.amount;
+    stats.tip_count += 1;
+    if new_tipper {
+        stats.unique_tippers += 1;
+    }
+    stats.last_tip_at = tip.timestamp;
+}
+
+pub fn next_pool_id(env: &Env) -> u64 {
+    let id: u64 = env
+        .storage()
+        .instance()
+        .get(&DataKey::PoolCounter)
+        .unwrap_or(0)
+        + 1;
+    env.storage().instance().set(&DataKey::PoolCounter, &id);
+    id
+}
+
+pub fn save_pool(env: &Env, pool: &MatchingPool) {
+    write_persistent(env, &DataKey::MatchingPool(pool.pool_id), pool);
+}
+
+pub fn get_pool(env: &Env, pool_id: u64) -> Option<MatchingPool> {
+    read_persistent(env, &DataKey::MatchingPool(pool_id))
+}
+
+pub fn get_artist_pools(env: &Env, artist: &Address) -> Vec<u64> {
+    read_persistent(env, &DataKey::ArtistPools(artist.clone())).unwrap_or(Vec::new(env))
+}
+
+pub fn set_artist_pools(env: &Env, artist: &Address, pool_ids: &Vec<u64>) {
+    write_persistent(env, &DataKey::ArtistPools(artist.clone()), pool_ids);
+}
+
+pub fn save_release(env: &Env, release: &TrackRelease) {
+    let key = DataKey::Release(release.artist.clone(), release.track_id.clone());
+    write_persistent(env, &key, release);
+}
+
+pub fn get_release(env: &Env, artist: &Address, track_id: &String) -> Option<TrackRelease> {
+    read_persistent(env, &DataKey::Release(artist.clone(), track_id.clone()))
+}
+
+pub fn next_presave_id(env: &Env) -> u64 {
+    let id: u64 = env
+        .storage()
+        .instance()
+        .get(&DataKey::PresaveCounter)
+        .unwrap_or(0)
+        + 1;
+    env.storage().instance().set(&DataKey::PresaveCounter, &id);
+    id
+}
+
+pub fn save_presave_tip(env: &Env, presave: &PresaveTip) {
+    write_persistent(env, &DataKey::PresaveTip(presave.presave_id), presave);
+}
+
+pub fn get_presave_tip(env: &Env, presave_id: u64) -> Option<PresaveTip> {
+    read_persistent(env, &DataKey::PresaveTip(presave_id))
+}
+
+pub fn save_voucher(env: &Env, voucher: &Voucher) {
+    write_persistent(env, &DataKey::Voucher(voucher.code_hash.clone()), voucher);
+}
+
+pub fn get_voucher(env: &Env, code_hash: &BytesN<32>) -> Option<Voucher> {
+    read_persistent(env, &DataKey::Voucher(code_hash.clone()))
+}
+
+pub fn save_redemption_commit(env: &Env, redeemer: &Address, commitment: &BytesN<32>) {
+    let key = DataKey::RedemptionCommit(redeemer.clone(), commitment.clone());
+    write_persistent(env, &key, &env.ledger().sequence());
+}
+
+pub fn get_redemption_commit(
+    env: &Env,
+    redeemer: &Address,
+    commitment: &BytesN<32>,
+) -> Option<u32> {
+    read_persistent(
+        env,
+        &DataKey::RedemptionCommit(redeemer.clone(), commitment.clone()),
+    )
+}
+
+pub fn remove_redemption_commit(env: &Env, redeemer: &Address, commitment: &BytesN<32>) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::RedemptionCommit(
+            redeemer.clone(),
+            commitment.clone(),
+        ));
+}
+
+pub fn set_hook(env: &Env, kind: HookKind, hook: &TipHook) {
+    env.storage().instance().set(&DataKey::Hook(kind), hook);
+}
+
+pub fn remove_hook(env: &Env, kind: HookKind) {
+    env.storage().instance().remove(&DataKey::Hook(kind));
+}
+
+pub fn get_hook(env: &Env, kind: HookKind) -> Option<TipHook> {
+    env.storage().instance().get(&DataKey::Hook(kind))
+}
+
+pub fn set_reward_rate(env: &Env, token: &Address, rate: u32) {
+    let key = DataKey::RewardRate(token.clone());
+    if rate > 0 {
+        write_persistent(env, &key, &rate);
+    } else {
+        env.storage().persistent().remove(&key);
+    }
+}
+
+pub fn get_reward_rate(env: &Env, token: &Address) -> u32 {
+    read_persistent(env, &DataKey::RewardRate(token.clone())).unwrap_or(0)
+}
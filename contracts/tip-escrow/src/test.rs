@@ -1,7 +1,10 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, token, Address, Env, Vec};
+use soroban_sdk::{
+    testutils::{Address as _, Ledger, MockAuth, MockAuthInvoke},
+    token, vec, Address, Bytes, Env, IntoVal, Vec,
+};
 
 fn create_token_contract<'a>(
     env: &Env,
@@ -14,6 +17,69 @@ fn create_token_contract<'a>(
     )
 }
 
+fn enable_token(client: &TipEscrowContractClient, admin: &Address, token: &Address) {
+    client.initialize(admin);
+    client.allow_asset(token, &1, &1_000_000);
+}
+
+fn redemption_commitment(
+    env: &Env,
+    code: &Bytes,
+    redeemer: &Address,
+    artist: &Address,
+) -> BytesN<32> {
+    let claim = (code.clone(), redeemer.clone(), artist.clone());
+    env.crypto().sha256(&claim.to_xdr(env)).into()
+}
+
+// Minimal stand-ins for the badge tracker and reward token hook targets
+#[contract]
+struct MockBadges;
+
+#[contractimpl]
+impl MockBadges {
+    pub fn record_tip(env: Env, user: Address, amount: i128, _is_genre_tip: bool) {
+        let total: i128 = env.storage().instance().get(&user).unwrap_or(0);
+        env.storage().instance().set(&user, &(total + amount));
+    }
+
+    pub fn tipped(env: Env, user: Address) -> i128 {
+        env.storage().instance().get(&user).unwrap_or(0)
+    }
+}
+
+#[contract]
+struct MockRewards;
+
+#[contractimpl]
+impl MockRewards {
+    pub fn mint_reward(env: Env, recipient: Address, amount: i128) {
+        let balance: i128 = env.storage().instance().get(&recipient).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&recipient, &(balance + amount));
+    }
+
+    pub fn balance(env: Env, account: Address) -> i128 {
+        env.storage().instance().get(&account).unwrap_or(0)
+    }
+}
+
+// Kept in its own module because contract function names must be unique per module
+mod broken {
+    use soroban_sdk::{contract, contractimpl, Address, Env};
+
+    #[contract]
+    pub struct BrokenHook;
+
+    #[contractimpl]
+    impl BrokenHook {
+        pub fn record_tip(_env: Env, _user: Address, _amount: i128, _is_genre_tip: bool) {
+            panic!("badge tracker unavailable");
+        }
+    }
+}
+
 #[test]
 fn test_send_tip_without_splits() {
     let env = Env::default();
@@ -27,9 +93,10 @@ fn test_send_tip_without_splits() {
     let artist = Address::generate(&env);
 
     let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
     token_admin.mint(&sender, &1000);
 
-    let tip_id = client.send_tip(&sender, &artist, &token.address, &100);
+    let tip_id = client.send_tip(&sender, &artist, &token.address, &100, &None, &None);
 
     assert_eq!(token.balance(&artist), 100);
     assert_eq!(token.balance(&sender), 900);
@@ -50,6 +117,7 @@ fn test_send_tip_with_splits() {
     let collaborator = Address::generate(&env);
 
     let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
     token_admin.mint(&sender, &1000);
 
     // Set 20% split to collaborator
@@ -60,7 +128,7 @@ fn test_send_tip_with_splits() {
     });
     client.set_royalty_splits(&artist, &splits);
 
-    client.send_tip(&sender, &artist, &token.address, &100);
+    client.send_tip(&sender, &artist, &token.address, &100, &None, &None);
 
     assert_eq!(token.balance(&collaborator), 20);
     assert_eq!(token.balance(&artist), 80);
@@ -92,7 +160,6 @@ fn test_get_royalty_splits() {
 }
 
 #[test]
-#[should_panic(expected = "Total splits exceed 100%")]
 fn test_invalid_splits_total() {
     let env = Env::default();
     env.mock_all_auths();
@@ -114,7 +181,10 @@ fn test_invalid_splits_total() {
         percentage: 5000,
     });
 
-    client.set_royalty_splits(&artist, &splits);
+    assert_eq!(
+        client.try_set_royalty_splits(&artist, &splits),
+        Err(Ok(Error::SplitsExceedTotal))
+    );
 }
 
 #[test]
@@ -130,12 +200,13 @@ fn test_create_and_get_escrow() {
     let artist = Address::generate(&env);
 
     let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
     token_admin.mint(&tipper, &1000);
 
     let asset = types::Asset::Token(token.address.clone());
 
     let amount = 200;
-    let escrow_id = client.create_escrow(&tipper, &artist, &amount, &asset);
+    let escrow_id = client.create_escrow(&tipper, &artist, &amount, &asset, &1000, &None);
 
     assert_eq!(token.balance(&tipper), 800);
     assert_eq!(token.balance(&contract_id), 200);
@@ -144,4 +215,1575 @@ fn test_create_and_get_escrow() {
     assert_eq!(escrow.tipper, tipper);
     assert_eq!(escrow.artist, artist);
     assert_eq!(escrow.amount, amount);
+    assert_eq!(escrow.status, types::EscrowStatus::Pending);
+    assert_eq!(escrow.expires_at, 1000);
+}
+
+fn setup_escrow<'a>(
+    env: &Env,
+) -> (
+    TipEscrowContractClient<'a>,
+    token::Client<'a>,
+    Address,
+    Address,
+    String,
+) {
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(env, &contract_id);
+
+    let admin = Address::generate(env);
+    let tipper = Address::generate(env);
+    let artist = Address::generate(env);
+
+    let (token, token_admin) = create_token_contract(env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&tipper, &1000);
+
+    let asset = types::Asset::Token(token.address.clone());
+    let escrow_id = client.create_escrow(&tipper, &artist, &300, &asset, &1000, &None);
+
+    (client, token, tipper, artist, escrow_id)
+}
+
+#[test]
+fn test_release_escrow_pays_artist() {
+    let env = Env::default();
+    let (client, token, tipper, artist, escrow_id) = setup_escrow(&env);
+
+    client.release_escrow(&escrow_id);
+
+    assert_eq!(token.balance(&artist), 300);
+    assert_eq!(token.balance(&tipper), 700);
+    assert_eq!(
+        client.get_escrow(&escrow_id).status,
+        types::EscrowStatus::Released
+    );
+
+    // A settled escrow cannot move funds again
+    assert_eq!(
+        client.try_release_escrow(&escrow_id),
+        Err(Ok(Error::EscrowNotPending))
+    );
+    assert_eq!(
+        client.try_refund_escrow(&escrow_id),
+        Err(Ok(Error::EscrowNotPending))
+    );
+}
+
+#[test]
+fn test_refund_escrow_returns_funds() {
+    let env = Env::default();
+    let (client, token, tipper, artist, escrow_id) = setup_escrow(&env);
+
+    client.refund_escrow(&escrow_id);
+
+    assert_eq!(token.balance(&tipper), 1000);
+    assert_eq!(token.balance(&artist), 0);
+    assert_eq!(
+        client.get_escrow(&escrow_id).status,
+        types::EscrowStatus::Refunded
+    );
+}
+
+#[test]
+fn test_reclaim_expired_escrow() {
+    let env = Env::default();
+    let (client, token, tipper, _, escrow_id) = setup_escrow(&env);
+
+    assert_eq!(
+        client.try_reclaim_expired_escrow(&escrow_id),
+        Err(Ok(Error::EscrowNotExpired))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    client.reclaim_expired_escrow(&escrow_id);
+
+    assert_eq!(token.balance(&tipper), 1000);
+    assert_eq!(
+        client.get_escrow(&escrow_id).status,
+        types::EscrowStatus::Expired
+    );
+}
+
+#[test]
+fn test_create_escrow_rejects_past_expiry() {
+    let env = Env::default();
+    let (client, token, tipper, artist, _) = setup_escrow(&env);
+
+    env.ledger().with_mut(|li| li.timestamp = 500);
+    let asset = types::Asset::Token(token.address.clone());
+    assert_eq!(
+        client.try_create_escrow(&tipper, &artist, &100, &asset, &500, &None),
+        Err(Ok(Error::InvalidExpiry))
+    );
+}
+
+#[test]
+fn test_arbiter_splits_disputed_escrow() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let tipper = Address::generate(&env);
+    let artist = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&tipper, &1000);
+
+    let asset = types::Asset::Token(token.address.clone());
+    let escrow_id = client.create_escrow(
+        &tipper,
+        &artist,
+        &400,
+        &asset,
+        &10_000,
+        &Some(arbiter.clone()),
+    );
+
+    let deadline = client.raise_dispute(&escrow_id, &artist);
+    assert_eq!(deadline, 604_800);
+
+    // Funds are frozen while the dispute is open
+    assert_eq!(
+        client.try_release_escrow(&escrow_id),
+        Err(Ok(Error::EscrowNotPending))
+    );
+
+    client.resolve_dispute(&escrow_id, &100);
+
+    assert_eq!(token.balance(&tipper), 700);
+    assert_eq!(token.balance(&artist), 300);
+    assert_eq!(token.balance(&contract_id), 0);
+
+    let escrow = client.get_escrow(&escrow_id);
+    assert_eq!(escrow.status, types::EscrowStatus::Resolved);
+    assert_eq!(escrow.disputed_by, Some(artist));
+}
+
+#[test]
+fn test_dispute_requires_party() {
+    let env = Env::default();
+    let (client, _, _, _, escrow_id) = setup_escrow(&env);
+
+    let stranger = Address::generate(&env);
+    assert_eq!(
+        client.try_raise_dispute(&escrow_id, &stranger),
+        Err(Ok(Error::Unauthorized))
+    );
+}
+
+#[test]
+fn test_expired_dispute_applies_default_outcome() {
+    let env = Env::default();
+    let (client, token, tipper, artist, escrow_id) = setup_escrow(&env);
+
+    client.set_dispute_config(&100, &types::DisputeOutcome::ReleaseToArtist);
+
+    client.raise_dispute(&escrow_id, &tipper);
+
+    assert_eq!(
+        client.try_settle_expired_dispute(&escrow_id),
+        Err(Ok(Error::DisputeDeadlineNotPassed))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 101);
+
+    // The arbiter (admin fallback) can no longer act after the deadline
+    assert_eq!(
+        client.try_resolve_dispute(&escrow_id, &0),
+        Err(Ok(Error::DisputeDeadlinePassed))
+    );
+
+    client.settle_expired_dispute(&escrow_id);
+
+    assert_eq!(token.balance(&artist), 300);
+    assert_eq!(
+        client.get_escrow(&escrow_id).status,
+        types::EscrowStatus::Released
+    );
+}
+
+#[test]
+fn test_admin_assigns_arbiter() {
+    let env = Env::default();
+    let (client, token, tipper, artist, escrow_id) = setup_escrow(&env);
+
+    let arbiter = Address::generate(&env);
+    assert_eq!(
+        client.try_initialize(&arbiter),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+
+    client.assign_arbiter(&escrow_id, &arbiter);
+    assert_eq!(client.get_escrow(&escrow_id).arbiter, Some(arbiter));
+
+    // Neither party may judge their own dispute
+    assert_eq!(
+        client.try_assign_arbiter(&escrow_id, &artist),
+        Err(Ok(Error::InvalidArbiter))
+    );
+    let asset = types::Asset::Token(token.address.clone());
+    assert_eq!(
+        client.try_create_escrow(&tipper, &artist, &100, &asset, &1000, &Some(tipper.clone())),
+        Err(Ok(Error::InvalidArbiter))
+    );
+}
+
+#[test]
+fn test_tip_history_is_indexed_and_paginated() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let fan_a = Address::generate(&env);
+    let fan_b = Address::generate(&env);
+    let artist = Address::generate(&env);
+    let other_artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&fan_a, &1000);
+    token_admin.mint(&fan_b, &1000);
+
+    let tips = [
+        (&fan_a, &artist, 10),
+        (&fan_b, &artist, 20),
+        (&fan_a, &other_artist, 30),
+        (&fan_a, &artist, 40),
+    ];
+    for (sender, to, amount) in tips.iter() {
+        client.send_tip(sender, to, &token.address, amount, &None, &None);
+    }
+
+    assert_eq!(client.get_tip_count(&types::TipIndex::All), 4);
+    assert_eq!(
+        client.get_tip_count(&types::TipIndex::Artist(artist.clone())),
+        3
+    );
+
+    let first_page = client.get_tips_by_artist(&artist, &0, &2);
+    assert_eq!(first_page.len(), 2);
+    assert_eq!(first_page.get(0).unwrap().amount, 10);
+    assert_eq!(first_page.get(1).unwrap().amount, 20);
+
+    let second_page = client.get_tips_by_artist(&artist, &2, &2);
+    assert_eq!(second_page.len(), 1);
+    assert_eq!(second_page.get(0).unwrap().amount, 40);
+
+    let by_sender = client.get_tips_by_sender(&fan_a, &0, &10);
+    assert_eq!(by_sender.len(), 3);
+    assert_eq!(by_sender.get(1).unwrap().artist, other_artist);
+
+    assert_eq!(client.get_tips(&3, &10).len(), 1);
+    assert_eq!(client.get_tips(&10, &10).len(), 0);
+
+    let tip = client.get_tip(&by_sender.get(2).unwrap().tip_id);
+    assert_eq!(tip.amount, 40);
+    assert_eq!(client.try_get_tip(&999), Err(Ok(Error::TipNotFound)));
+}
+
+#[test]
+fn test_tip_metadata_recorded_and_filterable_by_track() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sender = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sender, &1000);
+
+    let track_id = String::from_str(&env, "track-42");
+    let message = Bytes::from_slice(&env, b"love this one!");
+    let message_hash: BytesN<32> = env.crypto().sha256(&message).into();
+    let metadata = types::TipMetadata {
+        track_id: Some(track_id.clone()),
+        context: types::TipContext::Track,
+        message_hash: types::MessageHash::Sha256(message_hash),
+    };
+
+    let track_tip = client.send_tip(
+        &sender,
+        &artist,
+        &token.address,
+        &100,
+        &None,
+        &Some(metadata.clone()),
+    );
+    let profile_tip = client.send_tip(
+        &sender,
+        &artist,
+        &token.address,
+        &50,
+        &None,
+        &Some(types::TipMetadata {
+            track_id: None,
+            context: types::TipContext::Profile,
+            message_hash: types::MessageHash::None,
+        }),
+    );
+    let plain_tip = client.send_tip(&sender, &artist, &token.address, &25, &None, &None);
+
+    assert_eq!(client.get_tip(&track_tip).metadata, metadata);
+    assert_eq!(
+        client.get_tip(&profile_tip).metadata.context,
+        types::TipContext::Profile
+    );
+    assert_eq!(
+        client.get_tip(&plain_tip).metadata.context,
+        types::TipContext::Unspecified
+    );
+
+    // Another artist's track with the same id is indexed separately
+    let other_artist = Address::generate(&env);
+    let other_tip = client.send_tip(
+        &sender,
+        &other_artist,
+        &token.address,
+        &10,
+        &None,
+        &Some(metadata.clone()),
+    );
+
+    let by_track = client.get_tips_by_track(&artist, &track_id, &0, &10);
+    assert_eq!(by_track.len(), 1);
+    assert_eq!(by_track.get(0).unwrap().tip_id, track_tip);
+    assert_eq!(
+        client.get_tip_count(&types::TipIndex::Track(artist, track_id.clone())),
+        1
+    );
+    let other_by_track = client.get_tips_by_track(&other_artist, &track_id, &0, &10);
+    assert_eq!(other_by_track.len(), 1);
+    assert_eq!(other_by_track.get(0).unwrap().tip_id, other_tip);
+}
+
+#[test]
+fn test_tip_ids_are_unique_within_a_ledger() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sender = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sender, &1000);
+
+    let first = client.send_tip(&sender, &artist, &token.address, &100, &None, &None);
+    let second = client.send_tip(&sender, &artist, &token.address, &100, &None, &None);
+
+    assert_eq!(first, 1);
+    assert_eq!(second, 2);
+    assert_eq!(client.get_tip(&first).tip_id, first);
+    assert_eq!(client.get_tip(&second).tip_id, second);
+}
+
+#[test]
+fn test_send_tip_is_idempotent() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sender = Address::generate(&env);
+    let other_sender = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sender, &1000);
+    token_admin.mint(&other_sender, &1000);
+
+    let key = Some(BytesN::from_array(&env, &[7u8; 32]));
+    let tip_id = client.send_tip(&sender, &artist, &token.address, &100, &key, &None);
+    let retried = client.send_tip(&sender, &artist, &token.address, &100, &key, &None);
+
+    assert_eq!(retried, tip_id);
+    assert_eq!(token.balance(&sender), 900);
+    assert_eq!(token.balance(&artist), 100);
+    assert_eq!(client.get_tip_count(&types::TipIndex::All), 1);
+
+    // Keys are scoped per sender
+    let other_tip = client.send_tip(&other_sender, &artist, &token.address, &100, &key, &None);
+    assert_ne!(other_tip, tip_id);
+    assert_eq!(token.balance(&artist), 200);
+}
+
+#[test]
+fn test_send_tip_deducts_platform_fee_before_splits() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let collector = Address::generate(&env);
+    let sender = Address::generate(&env);
+    let artist = Address::generate(&env);
+    let collaborator = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sender, &10_000);
+
+    client.set_fee_config(&types::FeeConfig {
+        fee_percentage: 500, // 5%
+        waived_for_verified_artists: true,
+        fee_collector: collector.clone(),
+    });
+    client.set_asset_fee_bounds(&token.address, &2, &40);
+
+    let mut splits = Vec::new(&env);
+    splits.push_back(RoyaltySplit {
+        recipient: collaborator.clone(),
+        percentage: 5000,
+    });
+    client.set_royalty_splits(&artist, &splits);
+
+    let tip_id = client.send_tip(&sender, &artist, &token.address, &200, &None, &None);
+
+    assert_eq!(token.balance(&collector), 10);
+    assert_eq!(token.balance(&collaborator), 95);
+    assert_eq!(token.balance(&artist), 95);
+    assert_eq!(client.get_tip(&tip_id).fee, 10);
+
+    // Minimum floor and maximum cap
+    client.send_tip(&sender, &artist, &token.address, &20, &None, &None);
+    assert_eq!(token.balance(&collector), 12);
+    client.send_tip(&sender, &artist, &token.address, &2000, &None, &None);
+    assert_eq!(token.balance(&collector), 52);
+
+    // Bounds belong to the asset, so another asset is charged the plain percentage
+    let (other, other_admin) = create_token_contract(&env, &admin);
+    client.allow_asset(&other.address, &1, &1_000_000);
+    other_admin.mint(&sender, &10_000);
+    client.send_tip(&sender, &artist, &other.address, &2000, &None, &None);
+    assert_eq!(other.balance(&collector), 100);
+    assert_eq!(
+        client.try_set_asset_fee_bounds(&other.address, &50, &10),
+        Err(Ok(Error::InvalidFeeConfig))
+    );
+}
+
+#[test]
+fn test_fee_waived_for_listed_artist() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let collector = Address::generate(&env);
+    let sender = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sender, &1000);
+
+    client.set_fee_config(&types::FeeConfig {
+        fee_percentage: 1000,
+        waived_for_verified_artists: true,
+        fee_collector: collector.clone(),
+    });
+    client.set_fee_waiver(&artist, &true);
+
+    let tip_id = client.send_tip(&sender, &artist, &token.address, &100, &None, &None);
+
+    assert_eq!(token.balance(&collector), 0);
+    assert_eq!(token.balance(&artist), 100);
+    assert_eq!(client.get_tip(&tip_id).fee, 0);
+
+    assert_eq!(
+        client.try_set_fee_config(&types::FeeConfig {
+            fee_percentage: 10_001,
+            waived_for_verified_artists: false,
+            fee_collector: collector,
+        }),
+        Err(Ok(Error::InvalidFeeConfig))
+    );
+}
+
+#[test]
+fn test_admin_requires_initialization() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let token = Address::generate(&env);
+    assert_eq!(
+        client.try_allow_asset(&token, &1, &100),
+        Err(Ok(Error::NotInitialized))
+    );
+}
+
+#[test]
+fn test_send_tip_enforces_asset_allowlist() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sender = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    let (unlisted, unlisted_admin) = create_token_contract(&env, &admin);
+    token_admin.mint(&sender, &1000);
+    unlisted_admin.mint(&sender, &1000);
+
+    client.initialize(&admin);
+    client.allow_asset(&token.address, &10, &500);
+
+    assert_eq!(
+        client.try_send_tip(&sender, &artist, &unlisted.address, &100, &None, &None),
+        Err(Ok(Error::AssetNotAllowed))
+    );
+    assert_eq!(
+        client.try_send_tip(&sender, &artist, &token.address, &5, &None, &None),
+        Err(Ok(Error::AmountBelowMinimum))
+    );
+    assert_eq!(
+        client.try_send_tip(&sender, &artist, &token.address, &501, &None, &None),
+        Err(Ok(Error::AmountAboveMaximum))
+    );
+
+    client.send_tip(&sender, &artist, &token.address, &500, &None, &None);
+    assert_eq!(token.balance(&artist), 500);
+
+    client.remove_asset(&token.address);
+    assert_eq!(client.get_allowed_assets().len(), 0);
+    assert_eq!(
+        client.try_send_tip(&sender, &artist, &token.address, &100, &None, &None),
+        Err(Ok(Error::AssetNotAllowed))
+    );
+}
+
+#[test]
+fn test_native_asset_escrow() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let tipper = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    // Stands in for the network's native XLM asset contract
+    let (xlm, xlm_admin) = create_token_contract(&env, &admin);
+    xlm_admin.mint(&tipper, &1000);
+
+    client.initialize(&admin);
+    assert_eq!(
+        client.try_create_escrow(&tipper, &artist, &100, &types::Asset::Native, &1000, &None),
+        Err(Ok(Error::NativeAssetNotSet))
+    );
+
+    client.set_native_asset(&xlm.address);
+    client.allow_asset(&xlm.address, &1, &1000);
+
+    let escrow_id =
+        client.create_escrow(&tipper, &artist, &100, &types::Asset::Native, &1000, &None);
+    assert_eq!(xlm.balance(&contract_id), 100);
+    assert_eq!(client.get_escrow(&escrow_id).token, xlm.address);
+
+    // Re-registering the native asset does not change what existing escrows pay out in
+    let (other, _) = create_token_contract(&env, &admin);
+    client.set_native_asset(&other.address);
+    client.release_escrow(&escrow_id);
+    assert_eq!(xlm.balance(&artist), 100);
+}
+
+#[test]
+fn test_send_tips_to_multiple_artists() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sender = Address::generate(&env);
+    let artist_a = Address::generate(&env);
+    let artist_b = Address::generate(&env);
+    let collaborator = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sender, &1000);
+
+    let mut splits = Vec::new(&env);
+    splits.push_back(RoyaltySplit {
+        recipient: collaborator.clone(),
+        percentage: 1000,
+    });
+    client.set_royalty_splits(&artist_b, &splits);
+
+    let mut tips = Vec::new(&env);
+    tips.push_back((artist_a.clone(), 100));
+    tips.push_back((artist_b.clone(), 200));
+
+    let tip_ids = client.send_tips(&sender, &token.address, &tips, &None);
+
+    assert_eq!(tip_ids.len(), 2);
+    assert_eq!(client.get_tip(&tip_ids.get(0).unwrap()).artist, artist_a);
+    assert_eq!(client.get_tip(&tip_ids.get(1).unwrap()).artist, artist_b);
+    assert_eq!(token.balance(&artist_a), 100);
+    assert_eq!(token.balance(&artist_b), 180);
+    assert_eq!(token.balance(&collaborator), 20);
+    assert_eq!(token.balance(&sender), 700);
+}
+
+#[test]
+fn test_send_tips_is_all_or_nothing() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sender = Address::generate(&env);
+    let artist_a = Address::generate(&env);
+    let artist_b = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sender, &1000);
+
+    let mut tips = Vec::new(&env);
+    tips.push_back((artist_a.clone(), 100));
+    tips.push_back((artist_b.clone(), 0));
+
+    assert_eq!(
+        client.try_send_tips(&sender, &token.address, &tips, &None),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(token.balance(&sender), 1000);
+    assert_eq!(token.balance(&artist_a), 0);
+    assert_eq!(client.get_tip_count(&types::TipIndex::All), 0);
+
+    assert_eq!(
+        client.try_send_tips(&sender, &token.address, &Vec::new(&env), &None),
+        Err(Ok(Error::InvalidBatchSize))
+    );
+}
+
+#[test]
+fn test_accrued_splits_are_claimable() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sender = Address::generate(&env);
+    let artist = Address::generate(&env);
+    let producer = Address::generate(&env);
+    let vocalist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sender, &1000);
+
+    let mut splits = Vec::new(&env);
+    splits.push_back(RoyaltySplit {
+        recipient: producer.clone(),
+        percentage: 2000,
+    });
+    splits.push_back(RoyaltySplit {
+        recipient: vocalist.clone(),
+        percentage: 1000,
+    });
+    client.set_royalty_splits(&artist, &splits);
+    client.set_payout_mode(&artist, &types::PayoutMode::Accrue);
+
+    client.send_tip(&sender, &artist, &token.address, &100, &None, &None);
+    client.send_tip(&sender, &artist, &token.address, &200, &None, &None);
+
+    // Artist is paid directly, split shares stay in the contract
+    assert_eq!(token.balance(&artist), 210);
+    assert_eq!(token.balance(&producer), 0);
+    assert_eq!(token.balance(&contract_id), 90);
+    assert_eq!(client.get_claimable(&producer, &token.address), 60);
+    assert_eq!(client.get_total_claimable(&token.address), 90);
+
+    let balances = client.get_claimable_balances(&vocalist);
+    assert_eq!(balances.len(), 1);
+    assert_eq!(balances.get(0).unwrap(), (token.address.clone(), 30));
+
+    assert_eq!(client.claim(&producer, &token.address), 60);
+    assert_eq!(token.balance(&producer), 60);
+    assert_eq!(client.get_claimable(&producer, &token.address), 0);
+    assert_eq!(client.get_claimable_balances(&producer).len(), 0);
+    assert_eq!(client.get_total_claimable(&token.address), 30);
+
+    assert_eq!(
+        client.try_claim(&producer, &token.address),
+        Err(Ok(Error::NothingToClaim))
+    );
+}
+
+#[test]
+fn test_split_validation_errors() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let artist = Address::generate(&env);
+    let collaborator = Address::generate(&env);
+
+    let split = |recipient: &Address, percentage: u32| RoyaltySplit {
+        recipient: recipient.clone(),
+        percentage,
+    };
+
+    let mut duplicate = Vec::new(&env);
+    duplicate.push_back(split(&collaborator, 1000));
+    duplicate.push_back(split(&collaborator, 1000));
+    assert_eq!(
+        client.try_set_royalty_splits(&artist, &duplicate),
+        Err(Ok(Error::DuplicateSplitRecipient))
+    );
+
+    let mut zero = Vec::new(&env);
+    zero.push_back(split(&collaborator, 0));
+    assert_eq!(
+        client.try_set_royalty_splits(&artist, &zero),
+        Err(Ok(Error::ZeroSplitPercentage))
+    );
+
+    let mut self_split = Vec::new(&env);
+    self_split.push_back(split(&artist, 1000));
+    assert_eq!(
+        client.try_set_royalty_splits(&artist, &self_split),
+        Err(Ok(Error::ArtistInSplits))
+    );
+
+    let mut too_many = Vec::new(&env);
+    for _ in 0..11 {
+        too_many.push_back(split(&Address::generate(&env), 100));
+    }
+    assert_eq!(
+        client.try_set_royalty_splits(&artist, &too_many),
+        Err(Ok(Error::TooManySplitRecipients))
+    );
+
+    assert_eq!(client.get_split_version(&artist), 0);
+}
+
+#[test]
+fn test_split_versions_are_recorded_on_tips() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sender = Address::generate(&env);
+    let artist = Address::generate(&env);
+    let first_collab = Address::generate(&env);
+    let second_collab = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sender, &1000);
+
+    let unsplit_tip = client.send_tip(&sender, &artist, &token.address, &100, &None, &None);
+    assert_eq!(client.get_tip(&unsplit_tip).split_version, 0);
+
+    let mut splits = Vec::new(&env);
+    splits.push_back(RoyaltySplit {
+        recipient: first_collab.clone(),
+        percentage: 1000,
+    });
+    assert_eq!(client.set_royalty_splits(&artist, &splits), 1);
+    let first_tip = client.send_tip(&sender, &artist, &token.address, &100, &None, &None);
+
+    env.ledger().with_mut(|li| li.timestamp = 5000);
+    let mut splits = Vec::new(&env);
+    splits.push_back(RoyaltySplit {
+        recipient: second_collab.clone(),
+        percentage: 3000,
+    });
+    assert_eq!(client.set_royalty_splits(&artist, &splits), 2);
+    let second_tip = client.send_tip(&sender, &artist, &token.address, &100, &None, &None);
+
+    assert_eq!(client.get_tip(&first_tip).split_version, 1);
+    assert_eq!(client.get_tip(&second_tip).split_version, 2);
+    assert_eq!(token.balance(&first_collab), 10);
+    assert_eq!(token.balance(&second_collab), 30);
+
+    let original = client.get_split_config(&artist, &1);
+    assert_eq!(original.effective_from, 0);
+    assert_eq!(original.splits.get(0).unwrap().recipient, first_collab);
+    assert_eq!(client.get_split_config(&artist, &2).effective_from, 5000);
+    assert_eq!(
+        client.try_get_split_config(&artist, &3),
+        Err(Ok(Error::SplitVersionNotFound))
+    );
+}
+
+#[test]
+fn test_artist_and_supporter_aggregates() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let fan_a = Address::generate(&env);
+    let fan_b = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (usdc, usdc_admin) = create_token_contract(&env, &admin);
+    let (xlm, xlm_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &usdc.address);
+    client.allow_asset(&xlm.address, &1, &1_000_000);
+    usdc_admin.mint(&fan_a, &1000);
+    usdc_admin.mint(&fan_b, &1000);
+    xlm_admin.mint(&fan_b, &1000);
+
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    client.send_tip(&fan_a, &artist, &usdc.address, &50, &None, &None);
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    client.send_tip(&fan_a, &artist, &usdc.address, &25, &None, &None);
+    client.send_tip(&fan_b, &artist, &xlm.address, &300, &None, &None);
+    env.ledger().with_mut(|li| li.timestamp = 300);
+    client.send_tip(&fan_b, &artist, &usdc.address, &10, &None, &None);
+
+    let stats = client.get_artist_stats(&artist);
+    assert_eq!(stats.tip_count, 4);
+    assert_eq!(stats.unique_tippers, 2);
+    assert_eq!(stats.total_received, 385);
+    assert_eq!(stats.last_tip_at, 300);
+
+    let usdc_stats = client.get_artist_asset_stats(&artist, &usdc.address);
+    assert_eq!(usdc_stats.tip_count, 3);
+    assert_eq!(usdc_stats.unique_tippers, 2);
+    assert_eq!(usdc_stats.total_received, 85);
+
+    let xlm_stats = client.get_artist_asset_stats(&artist, &xlm.address);
+    assert_eq!(xlm_stats.tip_count, 1);
+    assert_eq!(xlm_stats.unique_tippers, 1);
+    assert_eq!(xlm_stats.last_tip_at, 200);
+
+    let supporter = client.get_supporter_stats(&artist, &fan_a);
+    assert_eq!(supporter.total_tipped, 75);
+    assert_eq!(supporter.tip_count, 2);
+    assert_eq!(supporter.first_tip_at, 100);
+    assert_eq!(supporter.last_tip_at, 200);
+    assert_eq!(
+        client.get_supporter_asset_total(&artist, &fan_b, &xlm.address),
+        300
+    );
+
+    let nobody = Address::generate(&env);
+    assert_eq!(client.get_artist_stats(&nobody), types::TipStats::default());
+}
+
+#[test]
+fn test_matching_pool_matches_tips_until_budget_runs_out() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sponsor = Address::generate(&env);
+    let fan = Address::generate(&env);
+    let artist = Address::generate(&env);
+    let other_artist = Address::generate(&env);
+    let collaborator = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sponsor, &150);
+    token_admin.mint(&fan, &1000);
+
+    let mut splits = Vec::new(&env);
+    splits.push_back(RoyaltySplit {
+        recipient: collaborator.clone(),
+        percentage: 1000,
+    });
+    client.set_royalty_splits(&artist, &splits);
+
+    let mut artists = Vec::new(&env);
+    artists.push_back(artist.clone());
+    let pool_id = client.create_matching_pool(
+        &sponsor,
+        &token.address,
+        &150,
+        &types::MatchingTerms {
+            artists,
+            match_ratio: 10000,
+            per_tip_cap: 80,
+            ends_at: 1000,
+        },
+    );
+    assert_eq!(token.balance(&contract_id), 150);
+
+    // Nothing is matched until the artist accepts the pool
+    let unaccepted = client.send_tip(&fan, &artist, &token.address, &10, &None, &None);
+    assert_eq!(client.get_tip(&unaccepted).matched, 0);
+    client.accept_matching_pool(&artist, &pool_id);
+    assert_eq!(
+        client.try_accept_matching_pool(&other_artist, &pool_id),
+        Err(Ok(Error::InvalidMatchingPool))
+    );
+
+    // Matched 1:1
+    let first = client.send_tip(&fan, &artist, &token.address, &50, &None, &None);
+    assert_eq!(client.get_tip(&first).matched, 50);
+    assert_eq!(token.balance(&artist), 99);
+    assert_eq!(token.balance(&collaborator), 11);
+
+    // Capped per tip
+    let second = client.send_tip(&fan, &artist, &token.address, &200, &None, &None);
+    assert_eq!(client.get_tip(&second).matched, 80);
+
+    // Only what is left of the budget
+    let third = client.send_tip(&fan, &artist, &token.address, &100, &None, &None);
+    assert_eq!(client.get_tip(&third).matched, 20);
+    assert_eq!(client.get_matching_pool(&pool_id).remaining_budget, 0);
+
+    let fourth = client.send_tip(&fan, &artist, &token.address, &100, &None, &None);
+    assert_eq!(client.get_tip(&fourth).matched, 0);
+
+    // Artists outside the pool are never matched
+    let unmatched = client.send_tip(&fan, &other_artist, &token.address, &100, &None, &None);
+    assert_eq!(client.get_tip(&unmatched).matched, 0);
+    assert_eq!(token.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_unaccepted_pools_do_not_use_up_artist_slots() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let griefer = Address::generate(&env);
+    let sponsor = Address::generate(&env);
+    let fan = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&griefer, &10);
+    token_admin.mint(&sponsor, &500);
+    token_admin.mint(&fan, &100);
+
+    let mut artists = Vec::new(&env);
+    artists.push_back(artist.clone());
+    let dust_terms = types::MatchingTerms {
+        artists: artists.clone(),
+        match_ratio: 1,
+        per_tip_cap: 1,
+        ends_at: u64::MAX,
+    };
+    for _ in 0..10 {
+        client.create_matching_pool(&griefer, &token.address, &1, &dust_terms);
+    }
+    assert!(client.get_artist_matching_pools(&artist).is_empty());
+
+    let pool_id = client.create_matching_pool(
+        &sponsor,
+        &token.address,
+        &500,
+        &types::MatchingTerms {
+            artists,
+            match_ratio: 10000,
+            per_tip_cap: 500,
+            ends_at: 1000,
+        },
+    );
+    client.accept_matching_pool(&artist, &pool_id);
+    // Accepting twice keeps a single entry
+    client.accept_matching_pool(&artist, &pool_id);
+    assert_eq!(
+        client.get_artist_matching_pools(&artist),
+        vec![&env, pool_id]
+    );
+
+    let tip_id = client.send_tip(&fan, &artist, &token.address, &100, &None, &None);
+    assert_eq!(client.get_tip(&tip_id).matched, 100);
+}
+
+#[test]
+fn test_self_tips_are_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sponsor = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sponsor, &1000);
+    token_admin.mint(&artist, &100);
+
+    let mut artists = Vec::new(&env);
+    artists.push_back(artist.clone());
+    let pool_id = client.create_matching_pool(
+        &sponsor,
+        &token.address,
+        &1000,
+        &types::MatchingTerms {
+            artists,
+            match_ratio: 10000,
+            per_tip_cap: 100,
+            ends_at: 1000,
+        },
+    );
+    client.accept_matching_pool(&artist, &pool_id);
+
+    assert_eq!(
+        client.try_send_tip(&artist, &artist, &token.address, &100, &None, &None),
+        Err(Ok(Error::SelfTip))
+    );
+    let mut batch = Vec::new(&env);
+    batch.push_back((artist.clone(), 100));
+    assert_eq!(
+        client.try_send_tips(&artist, &token.address, &batch, &None),
+        Err(Ok(Error::SelfTip))
+    );
+    assert_eq!(client.get_matching_pool(&pool_id).remaining_budget, 1000);
+    assert_eq!(token.balance(&artist), 100);
+}
+
+#[test]
+fn test_sponsor_reclaims_unspent_pool_budget() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sponsor = Address::generate(&env);
+    let fan = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&sponsor, &500);
+    token_admin.mint(&fan, &1000);
+
+    let mut artists = Vec::new(&env);
+    artists.push_back(artist.clone());
+    let pool_id = client.create_matching_pool(
+        &sponsor,
+        &token.address,
+        &500,
+        &types::MatchingTerms {
+            artists,
+            match_ratio: 5000,
+            per_tip_cap: 500,
+            ends_at: 1000,
+        },
+    );
+    client.accept_matching_pool(&artist, &pool_id);
+
+    client.send_tip(&fan, &artist, &token.address, &100, &None, &None);
+    assert_eq!(token.balance(&artist), 150);
+
+    assert_eq!(
+        client.try_reclaim_matching_pool(&pool_id),
+        Err(Ok(Error::PoolStillActive))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    // Expired pools stop matching
+    let late_tip = client.send_tip(&fan, &artist, &token.address, &100, &None, &None);
+    assert_eq!(client.get_tip(&late_tip).matched, 0);
+
+    assert_eq!(client.reclaim_matching_pool(&pool_id), 450);
+    assert_eq!(token.balance(&sponsor), 450);
+    assert_eq!(
+        client.try_reclaim_matching_pool(&pool_id),
+        Err(Ok(Error::PoolEmpty))
+    );
+}
+
+#[test]
+fn test_presave_tip_claimable_at_release() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let fan = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&fan, &1000);
+
+    let track_id = String::from_str(&env, "track-42");
+    client.schedule_release(&artist, &track_id, &1000, &2000);
+
+    let presave_id = client.send_presave_tip(&fan, &artist, &track_id, &token.address, &100);
+    assert_eq!(token.balance(&contract_id), 100);
+
+    // Artists cannot pre-save tip their own track
+    assert_eq!(
+        client.try_send_presave_tip(&artist, &artist, &track_id, &token.address, &100),
+        Err(Ok(Error::SelfTip))
+    );
+
+    assert_eq!(
+        client.try_claim_presave_tip(&presave_id),
+        Err(Ok(Error::PresaveNotClaimable))
+    );
+    assert_eq!(
+        client.try_refund_presave_tip(&presave_id),
+        Err(Ok(Error::PresaveNotRefundable))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    // No new pre-saves once the track is out
+    assert_eq!(
+        client.try_send_presave_tip(&fan, &artist, &track_id, &token.address, &100),
+        Err(Ok(Error::ReleaseAlreadyOut))
+    );
+
+    // Locked funds can still be claimed after the asset is delisted
+    client.remove_asset(&token.address);
+    let tip_id = client.claim_presave_tip(&presave_id);
+    assert_eq!(token.balance(&artist), 100);
+    assert_eq!(token.balance(&contract_id), 0);
+
+    let tip = client.get_tip(&tip_id);
+    assert_eq!(tip.sender, fan);
+    assert_eq!(tip.artist, artist);
+    assert_eq!(tip.metadata.track_id, Some(track_id.clone()));
+    assert_eq!(
+        client.get_tips_by_track(&artist, &track_id, &0, &10).len(),
+        1
+    );
+
+    let presave = client.get_presave_tip(&presave_id);
+    assert_eq!(presave.status, types::PresaveStatus::Claimed);
+    assert_eq!(presave.tip_id, Some(tip_id));
+    assert_eq!(
+        client.try_claim_presave_tip(&presave_id),
+        Err(Ok(Error::PresaveTipNotLocked))
+    );
+}
+
+#[test]
+fn test_release_track_ids_scoped_to_artist() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let fan = Address::generate(&env);
+    let artist = Address::generate(&env);
+    let squatter = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&fan, &1000);
+
+    // Another artist taking the same track id first does not block the real one
+    let track_id = String::from_str(&env, "track-42");
+    client.schedule_release(&squatter, &track_id, &1000, &2000);
+    client.schedule_release(&artist, &track_id, &1500, &3000);
+    assert_eq!(
+        client.try_schedule_release(&artist, &track_id, &1500, &3000),
+        Err(Ok(Error::ReleaseAlreadyScheduled))
+    );
+
+    let presave_id = client.send_presave_tip(&fan, &artist, &track_id, &token.address, &100);
+    assert_eq!(client.get_presave_tip(&presave_id).artist, artist);
+
+    // Neither can touch the other's release
+    client.cancel_release(&squatter, &track_id);
+    assert_eq!(
+        client.get_release(&artist, &track_id).status,
+        types::ReleaseStatus::Scheduled
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 1500);
+    client.claim_presave_tip(&presave_id);
+    assert_eq!(token.balance(&artist), 100);
+    assert_eq!(token.balance(&squatter), 0);
+}
+
+#[test]
+fn test_presave_tip_refundable_after_cancel_or_postponement() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let fan = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&fan, &1000);
+
+    let cancelled = String::from_str(&env, "cancelled");
+    client.schedule_release(&artist, &cancelled, &1000, &2000);
+    let first = client.send_presave_tip(&fan, &artist, &cancelled, &token.address, &100);
+    client.cancel_release(&artist, &cancelled);
+
+    client.refund_presave_tip(&first);
+    assert_eq!(token.balance(&fan), 1000);
+
+    let postponed = String::from_str(&env, "postponed");
+    client.schedule_release(&artist, &postponed, &1000, &2000);
+    let second = client.send_presave_tip(&fan, &artist, &postponed, &token.address, &200);
+
+    // Moving within the deadline keeps the tip locked
+    client.reschedule_release(&artist, &postponed, &1500);
+    assert_eq!(
+        client.try_refund_presave_tip(&second),
+        Err(Ok(Error::PresaveNotRefundable))
+    );
+
+    client.reschedule_release(&artist, &postponed, &2500);
+    env.ledger().with_mut(|li| li.timestamp = 2500);
+    assert_eq!(
+        client.try_claim_presave_tip(&second),
+        Err(Ok(Error::PresaveNotClaimable))
+    );
+
+    client.refund_presave_tip(&second);
+    assert_eq!(token.balance(&fan), 1000);
+    assert_eq!(
+        client.get_presave_tip(&second).status,
+        types::PresaveStatus::Refunded
+    );
+}
+
+#[test]
+fn test_voucher_redeemed_with_code() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let fan = Address::generate(&env);
+    let thief = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&buyer, &1000);
+
+    let code = Bytes::from_slice(&env, b"TIPTUNE-GIFT-1234");
+    let code_hash: BytesN<32> = env.crypto().sha256(&code).into();
+    client.create_voucher(&buyer, &token.address, &250, &code_hash, &1000);
+    assert_eq!(token.balance(&contract_id), 250);
+
+    assert_eq!(
+        client.try_create_voucher(&buyer, &token.address, &250, &code_hash, &1000),
+        Err(Ok(Error::VoucherExists))
+    );
+
+    let wrong_code = Bytes::from_slice(&env, b"TIPTUNE-GIFT-0000");
+    assert_eq!(
+        client.try_redeem_voucher(&fan, &wrong_code, &artist, &None),
+        Err(Ok(Error::VoucherNotFound))
+    );
+
+    // The code only works for a redeemer who committed to it in an earlier ledger
+    assert_eq!(
+        client.try_redeem_voucher(&fan, &code, &artist, &None),
+        Err(Ok(Error::RedemptionNotCommitted))
+    );
+    let commitment = redemption_commitment(&env, &code, &fan, &artist);
+    client.commit_voucher_redemption(&fan, &commitment);
+    assert_eq!(
+        client.try_redeem_voucher(&fan, &code, &artist, &None),
+        Err(Ok(Error::RedemptionNotCommitted))
+    );
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+
+    // Someone who copies the code from the reveal has no commitment of their own
+    assert_eq!(
+        client.try_redeem_voucher(&thief, &code, &artist, &None),
+        Err(Ok(Error::RedemptionNotCommitted))
+    );
+    assert_eq!(
+        client.try_redeem_voucher(&fan, &code, &thief, &None),
+        Err(Ok(Error::RedemptionNotCommitted))
+    );
+
+    // A prepaid voucher stays redeemable after its asset is delisted
+    client.remove_asset(&token.address);
+    let tip_id = client.redeem_voucher(&fan, &code, &artist, &None);
+    assert_eq!(token.balance(&artist), 250);
+    assert_eq!(token.balance(&contract_id), 0);
+    assert_eq!(client.get_tip(&tip_id).sender, fan);
+
+    let voucher = client.get_voucher(&code_hash);
+    assert_eq!(voucher.status, types::VoucherStatus::Redeemed);
+    assert_eq!(voucher.tip_id, Some(tip_id));
+    assert_eq!(
+        client.try_redeem_voucher(&fan, &code, &artist, &None),
+        Err(Ok(Error::VoucherNotActive))
+    );
+}
+
+#[test]
+fn test_expired_voucher_reclaimed_by_buyer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let fan = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&buyer, &1000);
+
+    let code = Bytes::from_slice(&env, b"unused-gift");
+    let code_hash: BytesN<32> = env.crypto().sha256(&code).into();
+    client.create_voucher(&buyer, &token.address, &300, &code_hash, &1000);
+
+    assert_eq!(
+        client.try_reclaim_voucher(&code_hash),
+        Err(Ok(Error::VoucherNotExpired))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    assert_eq!(
+        client.try_redeem_voucher(&fan, &code, &artist, &None),
+        Err(Ok(Error::VoucherExpired))
+    );
+
+    client.reclaim_voucher(&code_hash);
+    assert_eq!(token.balance(&buyer), 1000);
+    assert_eq!(
+        client.get_voucher(&code_hash).status,
+        types::VoucherStatus::Reclaimed
+    );
+}
+
+#[test]
+fn test_tip_hooks_record_badges_and_mint_rewards() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+    let badges = MockBadgesClient::new(&env, &env.register_contract(None, MockBadges));
+    let rewards = MockRewardsClient::new(&env, &env.register_contract(None, MockRewards));
+
+    let admin = Address::generate(&env);
+    let fan = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (usdc, usdc_admin) = create_token_contract(&env, &admin);
+    let (xlm, xlm_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &usdc.address);
+    client.allow_asset(&xlm.address, &1, &1_000_000);
+    usdc_admin.mint(&fan, &1000);
+    xlm_admin.mint(&fan, &1000);
+
+    client.set_hook(
+        &types::HookKind::Badge,
+        &badges.address,
+        &types::HookFailureMode::Revert,
+    );
+    client.set_hook(
+        &types::HookKind::Reward,
+        &rewards.address,
+        &types::HookFailureMode::Revert,
+    );
+    client.set_reward_rate(&usdc.address, &500); // 5%
+
+    client.send_tip(&fan, &artist, &usdc.address, &200, &None, &None);
+    assert_eq!(badges.tipped(&fan), 200);
+    assert_eq!(rewards.balance(&fan), 10);
+
+    // No reward rate for XLM, but the badge tracker still sees the tip
+    client.send_tip(&fan, &artist, &xlm.address, &300, &None, &None);
+    assert_eq!(badges.tipped(&fan), 500);
+    assert_eq!(rewards.balance(&fan), 10);
+
+    client.remove_hook(&types::HookKind::Reward);
+    client.send_tip(&fan, &artist, &usdc.address, &200, &None, &None);
+    assert_eq!(rewards.balance(&fan), 10);
+    assert_eq!(client.get_hook(&types::HookKind::Reward), None);
+}
+
+#[test]
+fn test_badge_hook_records_tips_on_badge_contract() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+    let badges = tip_nft_badge::TipNftBadgeContractClient::new(
+        &env,
+        &env.register_contract(None, tip_nft_badge::TipNftBadgeContract),
+    );
+
+    let admin = Address::generate(&env);
+    let fan = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&fan, &1000);
+
+    badges.initialize(&admin, &10000, &5000);
+    badges.set_escrow(&contract_id);
+    client.set_hook(
+        &types::HookKind::Badge,
+        &badges.address,
+        &types::HookFailureMode::Revert,
+    );
+
+    // Only the fan signs; the badge contract accepts the escrow as the direct caller
+    let transfer = [MockAuthInvoke {
+        contract: &token.address,
+        fn_name: "transfer",
+        args: (&fan, &artist, 100_i128).into_val(&env),
+        sub_invokes: &[],
+    }];
+    let send_tip = MockAuthInvoke {
+        contract: &contract_id,
+        fn_name: "send_tip",
+        args: (
+            &fan,
+            &artist,
+            &token.address,
+            100_i128,
+            None::<BytesN<32>>,
+            None::<types::TipMetadata>,
+        )
+            .into_val(&env),
+        sub_invokes: &transfer,
+    };
+    let tip_auth = [MockAuth {
+        address: &fan,
+        invoke: &send_tip,
+    }];
+
+    client
+        .mock_auths(&tip_auth)
+        .send_tip(&fan, &artist, &token.address, &100, &None, &None);
+    let stats = badges.get_user_stats(&fan);
+    assert_eq!(stats.tip_count, 1);
+    assert_eq!(stats.total_amount, 100);
+
+    // A badge contract that trusts another escrow rejects the record, and the tip with it
+    env.mock_all_auths();
+    badges.set_escrow(&Address::generate(&env));
+    assert_eq!(
+        client.mock_auths(&tip_auth).try_send_tip(
+            &fan,
+            &artist,
+            &token.address,
+            &100,
+            &None,
+            &None
+        ),
+        Err(Ok(Error::HookFailed))
+    );
+    assert_eq!(token.balance(&artist), 100);
+    assert_eq!(badges.get_user_stats(&fan).tip_count, 1);
+}
+
+#[test]
+fn test_failing_hook_reverts_or_is_skipped() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, TipEscrowContract);
+    let client = TipEscrowContractClient::new(&env, &contract_id);
+    let broken = env.register_contract(None, broken::BrokenHook);
+
+    let admin = Address::generate(&env);
+    let fan = Address::generate(&env);
+    let artist = Address::generate(&env);
+
+    let (token, token_admin) = create_token_contract(&env, &admin);
+    enable_token(&client, &admin, &token.address);
+    token_admin.mint(&fan, &1000);
+
+    client.set_hook(
+        &types::HookKind::Badge,
+        &broken,
+        &types::HookFailureMode::Revert,
+    );
+    assert_eq!(
+        client.try_send_tip(&fan, &artist, &token.address, &100, &None, &None),
+        Err(Ok(Error::HookFailed))
+    );
+    assert_eq!(token.balance(&fan), 1000);
+    assert_eq!(client.get_tip_count(&types::TipIndex::All), 0);
+
+    client.set_hook(
+        &types::HookKind::Badge,
+        &broken,
+        &types::HookFailureMode::Skip,
+    );
+    let tip_id = client.send_tip(&fan, &artist, &token.address, &100, &None, &None);
+    assert_eq!(token.balance(&artist), 100);
+    assert_eq!(client.get_tip(&tip_id).amount, 100);
 }
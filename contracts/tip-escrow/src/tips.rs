@@ -0,0 +1,172 @@
+use soroban_sdk::{token, Address, Env, Vec};
+
+use crate::types::{
+    Error, MessageHash, PayoutMode, RoyaltySplit, TipContext, TipMetadata, TipRecord,
+};
+use crate::{events, fees, hooks, matching, storage};
+
+const MAX_SPLIT_RECIPIENTS: u32 = 10;
+
+/// Reject tips in assets that are not allowlisted or fall outside the asset's limits
+pub fn check_asset_allowed(env: &Env, token_address: &Address, amount: i128) -> Result<(), Error> {
+    let config = storage::get_asset_config(env, token_address).ok_or(Error::AssetNotAllowed)?;
+    if amount < config.min_amount {
+        return Err(Error::AmountBelowMinimum);
+    }
+    if amount > config.max_amount {
+        return Err(Error::AmountAboveMaximum);
+    }
+    Ok(())
+}
+
+/// Validate, pay out and record a single tip credited to `sender`.
+/// Funds are taken from `from`, which is the sender for direct tips and the contract
+/// for tips it was holding. Callers are responsible for authorizing `from`.
+pub fn process_tip(
+    env: &Env,
+    from: &Address,
+    sender: &Address,
+    artist: &Address,
+    token_address: &Address,
+    amount: i128,
+    metadata: Option<TipMetadata>,
+) -> Result<u64, Error> {
+    if amount <= 0 {
+        return Err(Error::InvalidAmount);
+    }
+    // A tip to oneself moves nothing but would still draw sponsor matches and rewards
+    if sender == artist {
+        return Err(Error::SelfTip);
+    }
+    // Funds the contract already holds were checked when they came in. Checking again
+    // could strand them if the admin has since delisted the asset or changed its limits.
+    if *from != env.current_contract_address() {
+        check_asset_allowed(env, token_address, amount)?;
+    }
+    let metadata = metadata.unwrap_or(TipMetadata {
+        track_id: None,
+        context: TipContext::Unspecified,
+        message_hash: MessageHash::None,
+    });
+
+    let token_client = token::Client::new(env, token_address);
+    let tip_id = storage::next_tip_id(env);
+
+    // Platform fee comes off the top; splits apply to what is left
+    let fee = match fees::calculate_fee(env, artist, token_address, amount) {
+        Some((fee, collector)) => {
+            token_client.transfer(from, &collector, &fee);
+            fee
+        }
+        None => 0,
+    };
+    let net_amount = amount - fee;
+
+    let split_version = distribute(env, token_address, from, artist, net_amount);
+    let matched = matching::apply_matches(env, tip_id, artist, token_address, amount);
+
+    // Record tip
+    let tip = TipRecord {
+        tip_id,
+        sender: sender.clone(),
+        artist: artist.clone(),
+        token: token_address.clone(),
+        amount,
+        fee,
+        split_version,
+        matched,
+        metadata,
+        timestamp: env.ledger().timestamp(),
+    };
+    storage::save_tip(env, &tip);
+    storage::record_tip_stats(env, &tip);
+
+    events::tip_sent(env, &tip);
+    hooks::run_hooks(env, &tip)?;
+
+    Ok(tip_id)
+}
+
+/// Pay `amount` from `from` to an artist and their royalty split recipients.
+/// In `PayoutMode::Accrue` the split shares are moved into the contract in a single transfer
+/// and credited to each recipient's claimable balance instead of being pushed out.
+/// Returns the split version that was applied, or 0 if the artist has no splits.
+pub fn distribute(
+    env: &Env,
+    token_address: &Address,
+    from: &Address,
+    artist: &Address,
+    amount: i128,
+) -> u32 {
+    let token_client = token::Client::new(env, token_address);
+
+    // Check if artist has royalty splits configured
+    let (version, splits) = match storage::get_current_splits(env, artist) {
+        Some(config) => (config.version, config.splits),
+        None => {
+            // No splits, send everything to artist
+            if amount > 0 {
+                token_client.transfer(from, artist, &amount);
+            }
+            return 0;
+        }
+    };
+
+    let accrue = storage::get_payout_mode(env, artist) == PayoutMode::Accrue;
+    let mut remaining = amount;
+
+    for split in splits.iter() {
+        let split_amount = (amount * split.percentage as i128) / 10000;
+        if split_amount > 0 {
+            if accrue {
+                storage::add_claimable(env, &split.recipient, token_address, split_amount);
+                events::balance_credited(env, &split.recipient, token_address, split_amount);
+            } else {
+                token_client.transfer(from, &split.recipient, &split_amount);
+            }
+            remaining -= split_amount;
+        }
+    }
+
+    let contract = env.current_contract_address();
+    if accrue && *from != contract && amount > remaining {
+        token_client.transfer(from, &contract, &(amount - remaining));
+    }
+
+    // Send remaining to artist
+    if remaining > 0 {
+        token_client.transfer(from, artist, &remaining);
+    }
+
+    version
+}
+
+/// Check a proposed split configuration before it is stored
+pub fn validate_splits(artist: &Address, splits: &Vec<RoyaltySplit>) -> Result<(), Error> {
+    if splits.len() > MAX_SPLIT_RECIPIENTS {
+        return Err(Error::TooManySplitRecipients);
+    }
+
+    let mut total: u32 = 0;
+    for (i, split) in splits.iter().enumerate() {
+        if split.percentage == 0 {
+            return Err(Error::ZeroSplitPercentage);
+        }
+        if split.recipient == *artist {
+            return Err(Error::ArtistInSplits);
+        }
+        if splits
+            .iter()
+            .skip(i + 1)
+            .any(|other| other.recipient == split.recipient)
+        {
+            return Err(Error::DuplicateSplitRecipient);
+        }
+        total = total.saturating_add(split.percentage);
+    }
+
+    if total > 10000 {
+        return Err(Error::SplitsExceedTotal);
+    }
+    Ok(())
+}
@@ -1,14 +1,177 @@
-use soroban_sdk::{contracterror, contracttype, Address, String};
+use soroban_sdk::{contracterror, contracttype, Address, BytesN, String, Vec};
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TipRecord {
+    pub tip_id: u64,
     pub sender: Address,
     pub artist: Address,
+    pub token: Address,
     pub amount: i128,
+    pub fee: i128,             // Platform fee taken before the royalty split
+    pub split_version: u32,    // Artist split configuration applied, 0 when none was set
+    pub matched: i128,         // Added on top of the tip by sponsor matching pools
+    pub metadata: TipMetadata, // Context Unspecified when the client sent none
     pub timestamp: u64,
 }
 
+/// Where in the app a tip was sent from
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TipContext {
+    Unspecified,
+    Track,
+    LiveShow,
+    Profile,
+    Playlist,
+}
+
+/// Hash of a fan's off-chain message. Stands in for `Option<BytesN<32>>`, which
+/// soroban-sdk 21 cannot convert to XDR inside a contract type under testutils.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MessageHash {
+    None,
+    Sha256(BytesN<32>),
+}
+
+/// Details a client attaches to a tip. The fan's message text stays off-chain;
+/// only its SHA-256 hash is recorded.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TipMetadata {
+    pub track_id: Option<String>,
+    pub context: TipContext,
+    pub message_hash: MessageHash,
+}
+
+/// What a sponsor commits to when opening a matching pool
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MatchingTerms {
+    pub artists: Vec<Address>,
+    pub match_ratio: u32, // Basis points of each tip to match (10000 = 1:1)
+    pub per_tip_cap: i128,
+    pub ends_at: u64,
+}
+
+/// Sponsor budget that matches fan tips to a set of artists until it runs out or ends
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MatchingPool {
+    pub pool_id: u64,
+    pub sponsor: Address,
+    pub token: Address,
+    pub terms: MatchingTerms,
+    pub budget: i128,
+    pub remaining_budget: i128,
+}
+
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ReleaseStatus {
+    Scheduled,
+    Cancelled,
+}
+
+/// An upcoming track that fans can pre-save tip.
+/// If the release slips past `deadline` or is cancelled, locked tips become refundable.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrackRelease {
+    pub track_id: String,
+    pub artist: Address,
+    pub release_at: u64,
+    pub deadline: u64,
+    pub status: ReleaseStatus,
+}
+
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PresaveStatus {
+    Locked,
+    Claimed,
+    Refunded,
+}
+
+/// A tip held by the contract until its track is released
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PresaveTip {
+    pub presave_id: u64,
+    pub sender: Address,
+    pub artist: Address,
+    pub track_id: String,
+    pub token: Address,
+    pub amount: i128,
+    pub status: PresaveStatus,
+    pub created_at: u64,
+    pub tip_id: Option<u64>, // Set once the artist claims it as a regular tip
+}
+
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum VoucherStatus {
+    Active,
+    Redeemed,
+    Reclaimed,
+}
+
+/// Prepaid tip (gift card) unlocked by the preimage of `code_hash`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Voucher {
+    pub code_hash: BytesN<32>, // SHA-256 of the secret code
+    pub buyer: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub expires_at: u64,
+    pub status: VoucherStatus,
+    pub tip_id: Option<u64>, // Set when the voucher is redeemed
+}
+
+/// Running totals of tips received by an artist, either overall or in a single asset.
+/// The overall `total_received` adds raw amounts across assets; use the per-asset
+/// aggregate when the amount needs a unit.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TipStats {
+    pub total_received: i128,
+    pub tip_count: u64,
+    pub unique_tippers: u32,
+    pub last_tip_at: u64,
+}
+
+/// Lifetime totals of one fan's tips to one artist
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SupporterStats {
+    pub total_tipped: i128,
+    pub tip_count: u64,
+    pub first_tip_at: u64,
+    pub last_tip_at: u64,
+}
+
+/// On-chain mirror of the backend `FeeConfiguration`. The minimum and maximum fee are
+/// set per asset on `AssetConfig`, since a raw amount means something different in each.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeConfig {
+    pub fee_percentage: u32, // Basis points (100 = 1%)
+    pub waived_for_verified_artists: bool,
+    pub fee_collector: Address,
+}
+
+/// Secondary indexes over tip history, each an append-only list of tip ids
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TipIndex {
+    All,
+    Artist(Address),
+    Sender(Address),
+    Track(Address, String), // artist + track_id
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct RoyaltySplit {
@@ -16,18 +179,120 @@ pub struct RoyaltySplit {
     pub percentage: u32, // Basis points (100 = 1%)
 }
 
+/// One version of an artist's royalty splits
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SplitConfig {
+    pub version: u32,
+    pub splits: Vec<RoyaltySplit>,
+    pub effective_from: u64,
+}
+
+/// How an artist's royalty split recipients get paid
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PayoutMode {
+    Push,   // Transfer each share inside the tipper's transaction
+    Accrue, // Credit shares to claimable balances held by the contract
+}
+
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum Error {
     InvalidAmount = 1,
     EscrowNotFound = 2,
+    InvalidExpiry = 3,
+    EscrowNotPending = 4,
+    EscrowNotExpired = 5,
+    AlreadyInitialized = 6,
+    NotInitialized = 7,
+    Unauthorized = 8,
+    EscrowExpired = 9,
+    EscrowNotDisputed = 10,
+    DisputeDeadlinePassed = 11,
+    DisputeDeadlineNotPassed = 12,
+    TipNotFound = 13,
+    InvalidFeeConfig = 14,
+    AssetNotAllowed = 15,
+    AmountBelowMinimum = 16,
+    AmountAboveMaximum = 17,
+    NativeAssetNotSet = 18,
+    InvalidBatchSize = 19,
+    NothingToClaim = 20,
+    SplitsExceedTotal = 21,
+    DuplicateSplitRecipient = 22,
+    ZeroSplitPercentage = 23,
+    ArtistInSplits = 24,
+    TooManySplitRecipients = 25,
+    SplitVersionNotFound = 26,
+    InvalidMatchingPool = 27,
+    PoolNotFound = 28,
+    PoolStillActive = 29,
+    PoolEmpty = 30,
+    TooManyPools = 31,
+    ReleaseNotFound = 32,
+    ReleaseAlreadyScheduled = 33,
+    InvalidReleaseSchedule = 34,
+    ReleaseNotScheduled = 35,
+    ReleaseAlreadyOut = 36,
+    PresaveTipNotFound = 37,
+    PresaveTipNotLocked = 38,
+    PresaveNotClaimable = 39,
+    PresaveNotRefundable = 40,
+    VoucherExists = 41,
+    VoucherNotFound = 42,
+    VoucherNotActive = 43,
+    VoucherExpired = 44,
+    VoucherNotExpired = 45,
+    HookFailed = 46,
+    InvalidArbiter = 47,
+    SelfTip = 48,
+    RedemptionNotCommitted = 49,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Asset {
     Token(Address),
+    Native, // XLM, settled through the Stellar Asset Contract registered by the admin
+}
+
+/// Tip limits and platform fee bounds for an allowlisted asset, in the asset's smallest unit
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetConfig {
+    pub min_amount: i128,
+    pub max_amount: i128,
+    pub minimum_fee: i128,
+    pub maximum_fee: i128,
+}
+
+/// Lifecycle of an escrowed tip. Only `Pending` and `Disputed` escrows hold funds.
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EscrowStatus {
+    Pending,
+    Released,
+    Refunded,
+    Expired,
+    Disputed,
+    Resolved,
+}
+
+/// Where the funds of a dispute go when the arbiter misses the resolution deadline
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DisputeOutcome {
+    RefundTipper,
+    ReleaseToArtist,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisputeConfig {
+    pub resolution_window: u64, // Seconds the arbiter has to resolve a dispute
+    pub default_outcome: DisputeOutcome,
 }
 
 #[contracttype]
@@ -38,4 +303,34 @@ pub struct TipEscrow {
     pub artist: Address,
     pub amount: i128,
     pub asset: Asset,
+    pub token: Address, // Contract the asset resolved to when the escrow was funded
+    pub status: EscrowStatus,
+    pub created_at: u64,
+    pub expires_at: u64, // Tipper may reclaim a pending escrow from this time on
+    pub arbiter: Option<Address>, // Falls back to the contract admin when unset
+    pub disputed_by: Option<Address>,
+    pub dispute_deadline: Option<u64>,
+}
+
+/// Downstream contracts notified after every tip
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HookKind {
+    Badge,  // `record_tip(sender, amount, is_genre_tip)` on the badge tracker
+    Reward, // `mint_reward(sender, amount)` on the reward token
+}
+
+/// What happens to a tip when a hook call fails
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HookFailureMode {
+    Revert, // Fail the whole tip
+    Skip,   // Keep the tip and emit a `hook failed` event
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TipHook {
+    pub contract: Address,
+    pub on_failure: HookFailureMode,
 }
@@ -51,6 +51,7 @@ pub struct UserStats {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DataKey {
     Admin,
+    Escrow,
     UserStats(Address),
     UserBadges(Address),
     BadgeMinted(Address, u32), // user + badge_type ordinal
@@ -80,9 +81,35 @@ impl TipNftBadgeContract {
         env.storage().instance().set(&DataKey::TotalBadges, &0u64);
     }
 
-    /// Record a tip for a user (called by escrow/verification contract)
+    /// Register the escrow contract whose tips count towards badges
+    pub fn set_escrow(env: Env, escrow: Address) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(Error::Unauthorized)?;
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::Escrow, &escrow);
+        Ok(())
+    }
+
+    /// Record a tip for a user. Only the registered escrow contract can call this,
+    /// so badges cannot be earned with made-up tips.
     /// Updates stats for badge eligibility
-    pub fn record_tip(env: Env, user: Address, amount: i128, is_genre_tip: bool) {
+    pub fn record_tip(
+        env: Env,
+        user: Address,
+        amount: i128,
+        is_genre_tip: bool,
+    ) -> Result<(), Error> {
+        let escrow: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Escrow)
+            .ok_or(Error::Unauthorized)?;
+        escrow.require_auth();
+
         let mut stats: UserStats = env
             .storage()
             .persistent()
@@ -108,6 +135,7 @@ impl TipNftBadgeContract {
         env.storage()
             .persistent()
             .set(&DataKey::UserStats(user), &stats);
+        Ok(())
     }
 
     /// Check if a user is eligible for a specific badge type
@@ -172,7 +200,7 @@ impl TipNftBadgeContract {
         }
 
         // Check eligibility
-        if !Self::check_badge_eligibility(env.clone(), user.clone(), badge_type.clone()) {
+        if !Self::check_badge_eligibility(env.clone(), user.clone(), badge_type) {
             return Err(Error::NotEligible);
         }
 
@@ -198,11 +226,11 @@ impl TipNftBadgeContract {
                 n /= 10;
             }
         }
-        let badge_id = String::from_slice(&env, &buf[i..]);
+        let badge_id = String::from_bytes(&env, &buf[i..]);
 
         let metadata = BadgeMetadata {
             badge_id: badge_id.clone(),
-            badge_type: badge_type.clone(),
+            badge_type,
             owner: user.clone(),
             minted_at: env.ledger().timestamp(),
         };
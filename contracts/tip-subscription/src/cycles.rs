@@ -0,0 +1,126 @@
+use crate::types::{Subscription, SubscriptionFrequency};
+use crate::{DAY_IN_SECONDS, MONTH_IN_SECONDS, WEEK_IN_SECONDS, YEAR_IN_SECONDS};
+
+/// Length of a billing period. Calendar months vary, so they count as 30 days here.
+pub fn nominal_duration(frequency: &SubscriptionFrequency) -> u64 {
+    match frequency {
+        SubscriptionFrequency::Daily => DAY_IN_SECONDS,
+        SubscriptionFrequency::Weekly => WEEK_IN_SECONDS,
+        SubscriptionFrequency::Monthly | SubscriptionFrequency::CalendarMonthly => MONTH_IN_SECONDS,
+        SubscriptionFrequency::Yearly => YEAR_IN_SECONDS,
+        SubscriptionFrequency::Custom(seconds) => *seconds,
+    }
+}
+
+/// Start of the billing period after the one starting at `from`
+pub fn next_period_start(sub: &Subscription, from: u64) -> u64 {
+    match sub.frequency {
+        SubscriptionFrequency::CalendarMonthly => add_calendar_month(from, sub.started_at),
+        _ => from + nominal_duration(&sub.frequency),
+    }
+}
+
+/// Length of the period that ends at `next_payment_timestamp`
+pub fn current_period_length(sub: &Subscription) -> u64 {
+    match sub.frequency {
+        SubscriptionFrequency::CalendarMonthly => {
+            sub.next_payment_timestamp
+                - sub_calendar_month(sub.next_payment_timestamp, sub.started_at)
+        }
+        _ => nominal_duration(&sub.frequency),
+    }
+}
+
+/// Number of periods that have started by `now`, counting from the one starting at
+/// `next_payment_timestamp`, and the start of the period after them
+pub fn elapsed_periods(sub: &Subscription, now: u64) -> (u64, u64) {
+    if sub.frequency != SubscriptionFrequency::CalendarMonthly {
+        let period = nominal_duration(&sub.frequency);
+        let count = now.saturating_sub(sub.next_payment_timestamp) / period + 1;
+        return (count, sub.next_payment_timestamp + count * period);
+    }
+
+    let mut count = 1;
+    let mut next = next_period_start(sub, sub.next_payment_timestamp);
+    while next <= now {
+        next = next_period_start(sub, next);
+        count += 1;
+    }
+    (count, next)
+}
+
+/// Cost of settling `periods` periods, with how many of them are free trial periods and how
+/// many are charged at the intro price. Trial periods are used up first, then intro ones.
+pub fn price_periods(sub: &Subscription, periods: u32) -> (i128, u32, u32) {
+    let free = periods.min(sub.trial_periods_left);
+    let intro = (periods - free).min(sub.intro_periods_left);
+    let regular = periods - free - intro;
+    let amount = sub.intro_price * intro as i128 + sub.amount * regular as i128;
+    (amount, free, intro)
+}
+
+/// The anchor's day of the month, in the month after `from`, at the anchor's time of day.
+/// Anchors late in the month fall on the last day of shorter months.
+fn add_calendar_month(from: u64, anchor: u64) -> u64 {
+    let (year, month, _) = civil_from_days(from / DAY_IN_SECONDS);
+    let (_, _, anchor_day) = civil_from_days(anchor / DAY_IN_SECONDS);
+
+    let (year, month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    let day = anchor_day.min(days_in_month(year, month));
+
+    days_from_civil(year, month, day) * DAY_IN_SECONDS + anchor % DAY_IN_SECONDS
+}
+
+/// The anchor's day of the month, in the month before `from`
+fn sub_calendar_month(from: u64, anchor: u64) -> u64 {
+    let (year, month, _) = civil_from_days(from / DAY_IN_SECONDS);
+    let (_, _, anchor_day) = civil_from_days(anchor / DAY_IN_SECONDS);
+
+    let (year, month) = if month == 1 {
+        (year - 1, 12)
+    } else {
+        (year, month - 1)
+    };
+    let day = anchor_day.min(days_in_month(year, month));
+
+    days_from_civil(year, month, day) * DAY_IN_SECONDS + anchor % DAY_IN_SECONDS
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+// Conversions between days since the Unix epoch and (year, month, day) in the proleptic
+// Gregorian calendar, after Howard Hinnant's `civil_from_days` and `days_from_civil`
+
+fn civil_from_days(days: u64) -> (i64, u32, u32) {
+    let z = days as i64 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    (era * 146_097 + doe - 719_468) as u64
+}
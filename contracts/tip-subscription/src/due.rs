@@ -0,0 +1,147 @@
+use soroban_sdk::{Env, String, Vec};
+
+use crate::storage::{self, read_subscription};
+use crate::types::{BillingPolicy, Subscription, SubscriptionStatus};
+
+const BUCKET_SECONDS: u64 = 86_400; // Subscriptions are indexed by the day they fall due
+const MAX_BUCKETS_PER_SCAN: u64 = 30;
+
+/// Retries are spaced evenly across the grace period, after the initial failed attempt
+pub fn next_retry_at(sub: &Subscription, policy: &BillingPolicy) -> u64 {
+    let retry_interval = policy.grace_period / (policy.max_retries as u64 + 1);
+    sub.next_payment_timestamp
+        .saturating_add(retry_interval.saturating_mul(sub.failed_attempts as u64))
+}
+
+/// When `process_payment` next has something to do: charge, retry, lapse or expire.
+/// None for paused, cancelled and expired subscriptions.
+pub fn next_action_at(env: &Env, sub: &Subscription) -> Option<u64> {
+    match sub.status {
+        SubscriptionStatus::Active => Some(sub.next_payment_timestamp),
+        SubscriptionStatus::PastDue => {
+            let policy = storage::read_billing_policy(env, &sub.artist);
+            let lapses_at = sub
+                .next_payment_timestamp
+                .saturating_add(policy.grace_period);
+            if sub.failed_attempts > policy.max_retries {
+                Some(lapses_at)
+            } else {
+                Some(next_retry_at(sub, &policy).min(lapses_at))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// File a subscription under the day of its next action, unless it is filed there already.
+/// An entry left behind under another day is moved or dropped lazily by `take_due`.
+pub fn schedule(env: &Env, sub: &Subscription) {
+    let Some(at) = next_action_at(env, sub) else {
+        return;
+    };
+    let bucket = at / BUCKET_SECONDS;
+
+    if storage::read_due_filed(env, &sub.id) != Some(bucket) {
+        storage::file_due(env, bucket, &sub.id);
+    }
+
+    if storage::read_due_cursor(env).is_none_or(|cursor| bucket < cursor) {
+        storage::write_due_cursor(env, bucket);
+    }
+}
+
+/// Remove and return up to `limit` subscriptions whose next action is due, oldest day first.
+/// Reading stops as soon as `limit` are found. Callers are expected to process each one,
+/// which files it under its new due day.
+pub fn take_due(env: &Env, now: u64, limit: u32) -> Vec<String> {
+    let mut due = Vec::new(env);
+    let Some(first) = storage::read_due_cursor(env) else {
+        return due;
+    };
+    let last = (now / BUCKET_SECONDS).min(first + MAX_BUCKETS_PER_SCAN - 1);
+
+    let mut cursor = None;
+    for bucket in first..=last {
+        if due.len() >= limit {
+            cursor = cursor.or(Some(bucket)); // Not read yet
+            break;
+        }
+        if take_from_bucket(env, bucket, now, limit, &mut due) {
+            cursor = cursor.or(Some(bucket));
+        }
+    }
+
+    storage::write_due_cursor(env, cursor.unwrap_or(last + 1));
+    due
+}
+
+/// Take due subscriptions from one day until `due` holds `limit`.
+/// Returns whether the day still has entries to handle.
+fn take_from_bucket(env: &Env, bucket: u64, now: u64, limit: u32, due: &mut Vec<String>) -> bool {
+    let len = storage::due_len(env, bucket);
+    let mut head = storage::read_due_head(env, bucket);
+    if head >= len {
+        return false;
+    }
+
+    let mut position = head;
+    while position < len && due.len() < limit {
+        let kept = storage::read_due_entry(env, bucket, position)
+            .is_some_and(|id| keep_entry(env, bucket, position, id, now, due));
+        // Everything before the head has been handled
+        if !kept && head == position {
+            head += 1;
+        }
+        position += 1;
+    }
+
+    if head >= len {
+        storage::remove_due_bucket(env, bucket);
+        return false;
+    }
+    storage::write_due_head(env, bucket, head);
+    true
+}
+
+/// Take an entry that is due, or move or drop one that no longer belongs under `bucket`.
+/// Returns whether the entry stays where it is.
+fn keep_entry(
+    env: &Env,
+    bucket: u64,
+    position: u32,
+    id: String,
+    now: u64,
+    due: &mut Vec<String>,
+) -> bool {
+    // Left behind when the subscription was filed under another day
+    if storage::read_due_filed(env, &id) != Some(bucket) {
+        storage::remove_due_entry(env, bucket, position);
+        return false;
+    }
+    let Some(sub) = read_subscription(env, &id) else {
+        storage::remove_due_entry(env, bucket, position);
+        storage::remove_due_filed(env, &id);
+        return false;
+    };
+
+    match next_action_at(env, &sub) {
+        Some(at) if at <= now => {
+            storage::remove_due_entry(env, bucket, position);
+            storage::remove_due_filed(env, &id);
+            due.push_back(id);
+            false
+        }
+        Some(at) if at / BUCKET_SECONDS == bucket => true,
+        Some(_) => {
+            // Its next action moved without a payment, e.g. the billing policy changed
+            storage::remove_due_entry(env, bucket, position);
+            schedule(env, &sub);
+            false
+        }
+        None => {
+            storage::remove_due_entry(env, bucket, position);
+            storage::remove_due_filed(env, &id);
+            false
+        }
+    }
+}
@@ -1,8 +1,15 @@
 use soroban_sdk::{symbol_short, Address, Env, String};
 
-pub fn subscription_created(env: &Env, subscription_id: String, subscriber: Address) {
-    env.events()
-        .publish((symbol_short!("sub_crt"), subscription_id), subscriber);
+pub fn subscription_created(
+    env: &Env,
+    subscription_id: String,
+    payer: Address,
+    beneficiary: Address,
+) {
+    env.events().publish(
+        (symbol_short!("sub_crt"), subscription_id),
+        (payer, beneficiary),
+    );
 }
 
 pub fn payment_processed(env: &Env, subscription_id: String, amount: i128) {
@@ -10,6 +17,18 @@ pub fn payment_processed(env: &Env, subscription_id: String, amount: i128) {
         .publish((symbol_short!("sub_paid"), subscription_id), amount);
 }
 
+pub fn payment_failed(env: &Env, subscription_id: String, failed_attempts: u32) {
+    env.events().publish(
+        (symbol_short!("sub_fail"), subscription_id),
+        failed_attempts,
+    );
+}
+
+pub fn subscription_lapsed(env: &Env, subscription_id: String, subscriber: Address) {
+    env.events()
+        .publish((symbol_short!("sub_laps"), subscription_id), subscriber);
+}
+
 pub fn subscription_cancelled(env: &Env, subscription_id: String, subscriber: Address) {
     env.events()
         .publish((symbol_short!("sub_canc"), subscription_id), subscriber);
@@ -23,4 +42,48 @@ pub fn subscription_paused(env: &Env, subscription_id: String, subscriber: Addre
 pub fn subscription_resumed(env: &Env, subscription_id: String, subscriber: Address) {
     env.events()
         .publish((symbol_short!("sub_resm"), subscription_id), subscriber);
+}
+
+pub fn tier_created(env: &Env, artist: Address, tier_id: u32) {
+    env.events()
+        .publish((symbol_short!("tier_crt"), artist), tier_id);
+}
+
+pub fn tier_updated(env: &Env, artist: Address, tier_id: u32) {
+    env.events()
+        .publish((symbol_short!("tier_upd"), artist), tier_id);
+}
+
+pub fn keeper_paid(env: &Env, keeper: Address, collected: u32, amount: i128) {
+    env.events()
+        .publish((symbol_short!("kpr_paid"), keeper), (collected, amount));
+}
+
+pub fn trial_started(env: &Env, subscription_id: String, subscriber: Address, periods: u32) {
+    env.events().publish(
+        (symbol_short!("sub_trl"), subscription_id),
+        (subscriber, periods),
+    );
+}
+
+pub fn trial_converted(env: &Env, subscription_id: String, amount: i128) {
+    env.events()
+        .publish((symbol_short!("sub_conv"), subscription_id), amount);
+}
+
+pub fn plan_changed(env: &Env, subscription_id: String, amount: i128, proration: i128) {
+    env.events().publish(
+        (symbol_short!("sub_plan"), subscription_id),
+        (amount, proration),
+    );
+}
+
+pub fn subscription_expired(env: &Env, subscription_id: String, beneficiary: Address) {
+    env.events()
+        .publish((symbol_short!("sub_exp"), subscription_id), beneficiary);
+}
+
+pub fn subscription_transferred(env: &Env, subscription_id: String, from: Address, to: Address) {
+    env.events()
+        .publish((symbol_short!("sub_xfer"), subscription_id), (from, to));
 }
\ No newline at end of file
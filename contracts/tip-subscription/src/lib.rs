@@ -1,23 +1,43 @@
 #![no_std]
 
+pub mod cycles;
+pub mod due;
 pub mod events;
 pub mod storage;
 pub mod types;
 
 use soroban_sdk::{
-    contract, contractimpl, symbol_short, token, Address, Env, String,
+    contract, contractimpl, symbol_short, token, Address, Env, String, Vec,
+};
+use storage::{read_subscription, read_tier, write_subscription, write_tier};
+use types::{
+    BillingPolicy, DueResult, Error, IntervalBounds, KeeperConfig, Membership, PaymentOutcome,
+    PaymentRecord, Plan, Subscription, SubscriptionFrequency, SubscriptionIndex,
+    SubscriptionStatus, Tier, TierTerms,
 };
-use storage::{read_subscription, write_subscription};
-use types::{Error, Subscription, SubscriptionFrequency, SubscriptionStatus};
 
+const DAY_IN_SECONDS: u64 = 86_400;
 const WEEK_IN_SECONDS: u64 = 604_800;
 const MONTH_IN_SECONDS: u64 = 2_592_000;
+const YEAR_IN_SECONDS: u64 = 31_536_000;
+const MAX_DUE_BATCH: u32 = 25;
+const MAX_HISTORY: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 50;
+const MIN_INTERVAL_FLOOR: u64 = 3_600; // No artist can bill more often than hourly
+const MAX_KEEPER_SHARE_BPS: i128 = 1_000; // A keeper tip is at most 10% of the payment
+const MAX_GRACE_PERIOD: u64 = 2_592_000; // 30 days
+const MAX_RETRIES: u32 = 10;
+const MAX_CATCH_UP_PERIODS: u32 = 12;
 
 #[contract]
 pub struct TipSubscriptionContract;
 
 #[contractimpl]
 impl TipSubscriptionContract {
+    /// Open a subscription and charge its first period straight away, so the subscriber
+    /// is paid up from the start. Later periods are charged as each one begins. The
+    /// subscriber must approve this contract for at least one period beforehand;
+    /// creation fails with `PaymentFailed` otherwise.
     pub fn create_subscription(
         env: Env,
         subscriber: Address,
@@ -31,135 +51,991 @@ impl TipSubscriptionContract {
         if amount <= 0 {
             return Err(Error::InvalidAmount);
         }
+        check_frequency(&env, &artist, &frequency)?;
+
+        let draft = new_subscription(
+            &env,
+            subscriber.clone(),
+            subscriber,
+            artist,
+            token,
+            amount,
+            frequency,
+        );
+        open_subscription(&env, draft, None)
+    }
+
+    /// Subscribe at one of the artist's tiers. The first period is charged on subscribing
+    /// unless it falls in a free trial. The tier's price and frequency are re-read at
+    /// every payment, so tier updates apply from the next billing cycle.
+    /// A tier's trial and intro pricing apply to a fan's first trial with each artist only.
+    pub fn subscribe(
+        env: Env,
+        subscriber: Address,
+        artist: Address,
+        tier_id: u32,
+    ) -> Result<String, Error> {
+        subscriber.require_auth();
 
-        let count_key = symbol_short!("sub_cnt");
-        let count: u32 = env.storage().instance().get(&count_key).unwrap_or(0);
-        let next_count = count + 1;
-        env.storage().instance().set(&count_key, &next_count);
+        let tier = join_tier(&env, &artist, tier_id)?;
+        let draft = new_subscription(
+            &env,
+            subscriber.clone(),
+            subscriber,
+            artist,
+            tier.terms.token.clone(),
+            tier.terms.price,
+            tier.terms.frequency.clone(),
+        );
+        open_subscription(&env, draft, Some(&tier))
+    }
 
-        let mut buffer = [0u8; 10];
-        let mut num = next_count;
-        let mut len = 0;
-        while num > 0 {
-            buffer[len] = b'0' + (num % 10) as u8;
-            num /= 10;
-            len += 1;
+    /// Buy a tier subscription for someone else. The payer is billed, starting with the
+    /// first period at purchase, and controls the subscription, while the beneficiary
+    /// holds the membership. A gift for a fixed number of periods ends on its own once
+    /// they are used; without one it renews until the payer cancels.
+    pub fn gift_subscription(
+        env: Env,
+        payer: Address,
+        beneficiary: Address,
+        artist: Address,
+        tier_id: u32,
+        term_periods: Option<u32>,
+    ) -> Result<String, Error> {
+        payer.require_auth();
+
+        if term_periods == Some(0) {
+            return Err(Error::InvalidTerm);
         }
-        for i in 0..(len / 2) {
-            buffer.swap(i, len - 1 - i);
+
+        let tier = join_tier(&env, &artist, tier_id)?;
+        let mut draft = new_subscription(
+            &env,
+            payer,
+            beneficiary,
+            artist,
+            tier.terms.token.clone(),
+            tier.terms.price,
+            tier.terms.frequency.clone(),
+        );
+        draft.periods_left = term_periods;
+        open_subscription(&env, draft, Some(&tier))
+    }
+
+    /// Hand a subscription's membership to someone else. Billing stays with the payer.
+    pub fn transfer_subscription(
+        env: Env,
+        subscription_id: String,
+        new_beneficiary: Address,
+    ) -> Result<(), Error> {
+        let mut sub =
+            read_subscription(&env, &subscription_id).ok_or(Error::SubscriptionNotFound)?;
+        sub.beneficiary.require_auth();
+
+        if matches!(
+            sub.status,
+            SubscriptionStatus::Cancelled | SubscriptionStatus::Expired
+        ) {
+            return Err(Error::InvalidStatus);
         }
-        let sub_id = String::from_bytes(&env, &buffer[..len]);
 
-        let current_time = env.ledger().timestamp();
-        let duration = match frequency {
-            SubscriptionFrequency::Weekly => WEEK_IN_SECONDS,
-            SubscriptionFrequency::Monthly => MONTH_IN_SECONDS,
-        };
-        let next_payment_timestamp = current_time + duration;
+        let previous = sub.beneficiary;
+        sub.beneficiary = new_beneficiary.clone();
+        write_subscription(&env, &subscription_id, &sub);
+        storage::index_beneficiary(&env, &sub);
+        storage::remove_membership(&env, &previous, &sub.artist, &subscription_id);
+        storage::add_membership(&env, &new_beneficiary, &sub.artist, &subscription_id);
 
-        let subscription = Subscription {
-            id: sub_id.clone(),
-            subscriber: subscriber.clone(),
-            artist: artist.clone(),
-            token: token.clone(),
-            amount,
-            frequency,
-            status: SubscriptionStatus::Active,
-            next_payment_timestamp,
-        };
+        events::subscription_transferred(&env, subscription_id, previous, new_beneficiary);
 
-        write_subscription(&env, &sub_id, &subscription);
+        Ok(())
+    }
+
+    /// Register a new subscription tier. Tier ids are numbered per artist from 1.
+    pub fn create_tier(env: Env, artist: Address, terms: TierTerms) -> Result<u32, Error> {
+        artist.require_auth();
+        validate_tier_terms(&terms)?;
+        check_frequency(&env, &artist, &terms.frequency)?;
+
+        let tier_id = storage::next_tier_id(&env, &artist);
+        write_tier(
+            &env,
+            &Tier {
+                tier_id,
+                artist: artist.clone(),
+                terms,
+                current_subscribers: 0,
+                is_active: true,
+            },
+        );
 
-        events::subscription_created(&env, sub_id.clone(), subscriber);
+        events::tier_created(&env, artist, tier_id);
 
-        Ok(sub_id)
+        Ok(tier_id)
     }
 
-    pub fn process_payment(env: Env, subscription_id: String) -> Result<(), Error> {
-        let mut sub = read_subscription(&env, &subscription_id).ok_or(Error::SubscriptionNotFound)?;
+    /// Change a tier's price, frequency, cap or perks. Existing subscribers keep their
+    /// current cycle and move to the new terms at their next payment. The token is fixed.
+    pub fn update_tier(
+        env: Env,
+        artist: Address,
+        tier_id: u32,
+        terms: TierTerms,
+    ) -> Result<(), Error> {
+        artist.require_auth();
+        validate_tier_terms(&terms)?;
+        check_frequency(&env, &artist, &terms.frequency)?;
+
+        let mut tier = read_tier(&env, &artist, tier_id).ok_or(Error::TierNotFound)?;
+        if terms.token != tier.terms.token {
+            return Err(Error::InvalidTier);
+        }
+
+        tier.terms = terms;
+        write_tier(&env, &tier);
+
+        events::tier_updated(&env, artist, tier_id);
+
+        Ok(())
+    }
+
+    /// Open or close a tier to new subscribers. Existing subscriptions are unaffected.
+    pub fn set_tier_active(
+        env: Env,
+        artist: Address,
+        tier_id: u32,
+        is_active: bool,
+    ) -> Result<(), Error> {
+        artist.require_auth();
+
+        let mut tier = read_tier(&env, &artist, tier_id).ok_or(Error::TierNotFound)?;
+        tier.is_active = is_active;
+        write_tier(&env, &tier);
+
+        events::tier_updated(&env, artist, tier_id);
+
+        Ok(())
+    }
+
+    pub fn get_tier(env: Env, artist: Address, tier_id: u32) -> Result<Tier, Error> {
+        read_tier(&env, &artist, tier_id).ok_or(Error::TierNotFound)
+    }
+
+    pub fn get_tiers(env: Env, artist: Address) -> Vec<Tier> {
+        storage::read_tiers(&env, &artist)
+    }
+
+    /// Charge the next period once it is due. Payments are pulled from the allowance the
+    /// subscriber granted this contract, so anyone may call this. A failed pull moves the
+    /// subscription to `PastDue`; retries are spread over the artist's grace period and the
+    /// subscription is cancelled by the first call after the grace period is over.
+    pub fn process_payment(env: Env, subscription_id: String) -> Result<PaymentOutcome, Error> {
+        let sub = read_subscription(&env, &subscription_id).ok_or(Error::SubscriptionNotFound)?;
+        settle(&env, subscription_id, sub).map(|(outcome, _)| outcome)
+    }
+
+    /// Settle up to `limit` subscriptions that are due, oldest first. Anyone may run a
+    /// batch, but only keepers the admin approved are tipped from the keeper pool, and
+    /// only for payments collected from someone other than the artist.
+    pub fn process_due(env: Env, keeper: Address, limit: u32) -> Vec<DueResult> {
+        keeper.require_auth();
+
+        let mut results = Vec::new(&env);
+        let mut collected = Vec::new(&env);
+        let now = env.ledger().timestamp();
+        for subscription_id in due::take_due(&env, now, limit.min(MAX_DUE_BATCH)).iter() {
+            let Some(sub) = read_subscription(&env, &subscription_id) else {
+                continue;
+            };
+            let self_billed = sub.payer == sub.artist;
+            // Only subscriptions with an action due are taken, so settling cannot be early
+            if let Ok((outcome, amount)) = settle(&env, subscription_id.clone(), sub) {
+                if outcome == PaymentOutcome::Paid && !self_billed && amount > 0 {
+                    collected.push_back(amount);
+                }
+                results.push_back(DueResult {
+                    subscription_id,
+                    outcome,
+                });
+            }
+        }
+
+        pay_keeper(&env, &keeper, &collected);
+        results
+    }
+
+    /// Set the contract admin, who configures the keeper reward
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        if storage::has_admin(&env) {
+            return Err(Error::AlreadyInitialized);
+        }
+        storage::write_admin(&env, &admin);
+        Ok(())
+    }
+
+    /// Set the token and amount paid to keepers per payment collected
+    pub fn set_keeper_config(env: Env, config: KeeperConfig) -> Result<(), Error> {
+        let admin = storage::read_admin(&env).ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+
+        if config.tip_per_item < 0 {
+            return Err(Error::InvalidAmount);
+        }
+        // The pool is held in a single token, so it cannot change while funded
+        if storage::read_keeper_config(&env).is_some_and(|current| current.token != config.token)
+            && storage::read_keeper_pool(&env) > 0
+        {
+            return Err(Error::KeeperPoolFunded);
+        }
+
+        storage::write_keeper_config(&env, &config);
+        Ok(())
+    }
+
+    /// Approve or revoke a keeper's tips from the pool. Payments can be made by anyone
+    /// to anyone, so tips cannot be earned without the admin vouching for the keeper.
+    pub fn set_keeper(env: Env, keeper: Address, approved: bool) -> Result<(), Error> {
+        let admin = storage::read_admin(&env).ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+
+        storage::write_keeper_approved(&env, &keeper, approved);
+        Ok(())
+    }
+
+    pub fn is_keeper(env: Env, keeper: Address) -> bool {
+        storage::is_keeper_approved(&env, &keeper)
+    }
+
+    pub fn get_keeper_config(env: Env) -> Option<KeeperConfig> {
+        storage::read_keeper_config(&env)
+    }
+
+    /// Add funds to the keeper pool. Anyone may top it up.
+    pub fn fund_keeper_pool(env: Env, funder: Address, amount: i128) -> Result<(), Error> {
+        funder.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let config = storage::read_keeper_config(&env).ok_or(Error::KeeperNotConfigured)?;
+
+        token::Client::new(&env, &config.token).transfer(
+            &funder,
+            &env.current_contract_address(),
+            &amount,
+        );
+        storage::write_keeper_pool(&env, storage::read_keeper_pool(&env) + amount);
+        Ok(())
+    }
+
+    pub fn get_keeper_pool(env: Env) -> i128 {
+        storage::read_keeper_pool(&env)
+    }
+
+    /// Set how long an artist's past-due subscribers keep their subscription and how many
+    /// times a failed payment is retried within that window. The grace period is at most
+    /// 30 days, with up to 10 retries and 12 missed periods caught up.
+    pub fn set_billing_policy(
+        env: Env,
+        artist: Address,
+        policy: BillingPolicy,
+    ) -> Result<(), Error> {
+        artist.require_auth();
+
+        if policy.grace_period > MAX_GRACE_PERIOD
+            || policy.max_retries > MAX_RETRIES
+            || policy.max_catch_up_periods > MAX_CATCH_UP_PERIODS
+        {
+            return Err(Error::InvalidBillingPolicy);
+        }
+
+        storage::write_billing_policy(&env, &artist, &policy);
+        Ok(())
+    }
+
+    pub fn get_billing_policy(env: Env, artist: Address) -> BillingPolicy {
+        storage::read_billing_policy(&env, &artist)
+    }
+
+    /// Limit the billing intervals fans can subscribe to an artist at. The shortest
+    /// interval allowed is an hour. Existing subscriptions keep their frequency.
+    pub fn set_interval_bounds(
+        env: Env,
+        artist: Address,
+        bounds: IntervalBounds,
+    ) -> Result<(), Error> {
+        artist.require_auth();
+
+        if bounds.min_interval < MIN_INTERVAL_FLOOR || bounds.max_interval < bounds.min_interval {
+            return Err(Error::InvalidFrequency);
+        }
+
+        storage::write_interval_bounds(&env, &artist, &bounds);
+        Ok(())
+    }
+
+    pub fn get_interval_bounds(env: Env, artist: Address) -> IntervalBounds {
+        storage::read_interval_bounds(&env, &artist)
+    }
+
+    /// Whether the fan has already had their trial or intro offer with this artist
+    pub fn has_used_trial(env: Env, subscriber: Address, artist: Address) -> bool {
+        storage::has_used_trial(&env, &subscriber, &artist)
+    }
+
+    /// Move a subscription to a new amount or tier without losing its id or history.
+    /// The rest of the current cycle is prorated: an upgrade is charged the difference
+    /// now and a downgrade is credited toward the next payments. A new frequency takes
+    /// over from the next payment, and any intro pricing left ends with the old plan.
+    /// Returns the amount charged, or the credit granted as a negative amount.
+    pub fn change_plan(
+        env: Env,
+        subscription_id: String,
+        plan: Plan,
+        new_frequency: Option<SubscriptionFrequency>,
+    ) -> Result<i128, Error> {
+        let sub = read_subscription(&env, &subscription_id).ok_or(Error::SubscriptionNotFound)?;
+        sub.payer.require_auth();
 
         if sub.status != SubscriptionStatus::Active {
             return Err(Error::InvalidStatus);
         }
-
+        // Periods already due are billed on the old plan first
         let current_time = env.ledger().timestamp();
-        if current_time < sub.next_payment_timestamp {
-            return Err(Error::PaymentTooEarly);
+        if current_time >= sub.next_payment_timestamp {
+            return Err(Error::PaymentDue);
         }
 
-        let token_client = token::Client::new(&env, &sub.token);
-        token_client.transfer(
-            &sub.subscriber,
-            &sub.artist,
-            &sub.amount,
-        );
+        let mut updated = sub.clone();
+        match plan {
+            Plan::Amount(amount) => {
+                if amount <= 0 {
+                    return Err(Error::InvalidAmount);
+                }
+                updated.amount = amount;
+                updated.frequency = new_frequency.unwrap_or(sub.frequency.clone());
+                updated.tier_id = None;
+                check_frequency(&env, &sub.artist, &updated.frequency)?;
+            }
+            Plan::Tier(tier_id) => {
+                let tier = read_tier(&env, &sub.artist, tier_id).ok_or(Error::TierNotFound)?;
+                if tier.terms.token != sub.token {
+                    return Err(Error::InvalidTier);
+                }
+                if new_frequency.is_some_and(|frequency| frequency != tier.terms.frequency) {
+                    return Err(Error::InvalidFrequency);
+                }
+                updated.amount = tier.terms.price;
+                updated.frequency = tier.terms.frequency;
+                updated.tier_id = Some(tier_id);
+            }
+        }
+        if updated.tier_id != sub.tier_id {
+            leave_tier(&env, &sub);
+            if let Some(tier_id) = updated.tier_id {
+                join_tier(&env, &sub.artist, tier_id)?;
+            }
+        }
+        if updated.frequency != sub.frequency {
+            updated.started_at = sub.next_payment_timestamp;
+        }
 
-        let duration = match sub.frequency {
-            SubscriptionFrequency::Weekly => WEEK_IN_SECONDS,
-            SubscriptionFrequency::Monthly => MONTH_IN_SECONDS,
+        // Nothing was paid for a free trial period, so there is nothing to prorate
+        let proration = if sub.period_price == 0 {
+            0
+        } else {
+            let remaining = (sub.next_payment_timestamp - current_time) as i128;
+            let old_period = cycles::current_period_length(&sub) as i128;
+            let new_period = cycles::current_period_length(&updated) as i128;
+            updated.period_price = updated.amount;
+            updated.amount * remaining / new_period - sub.period_price * remaining / old_period
         };
-        sub.next_payment_timestamp = current_time + duration;
+        updated.intro_periods_left = 0;
 
-        write_subscription(&env, &subscription_id, &sub);
+        if proration > 0 {
+            let credit_used = updated.credit.min(proration);
+            let charge = proration - credit_used;
+            if charge > 0 && !pull_payment(&env, &updated, charge) {
+                return Err(Error::PaymentFailed);
+            }
+            updated.credit -= credit_used;
+            if charge > 0 {
+                updated.last_paid_at = current_time;
+                record_payment(&env, &updated, charge, 0);
+            }
+        } else {
+            updated.credit -= proration;
+        }
 
-        events::payment_processed(&env, subscription_id, sub.amount);
+        write_subscription(&env, &subscription_id, &updated);
+        events::plan_changed(&env, subscription_id, updated.amount, proration);
 
-        Ok(())
+        Ok(proration)
     }
 
     pub fn cancel_subscription(env: Env, subscription_id: String) -> Result<(), Error> {
         let mut sub = read_subscription(&env, &subscription_id).ok_or(Error::SubscriptionNotFound)?;
-        sub.subscriber.require_auth();
+        sub.payer.require_auth();
 
         // FIX: Prevent double-cancel
-        if sub.status == SubscriptionStatus::Cancelled {
+        if matches!(
+            sub.status,
+            SubscriptionStatus::Cancelled | SubscriptionStatus::Expired
+        ) {
             return Err(Error::InvalidStatus);
         }
 
-        sub.status = SubscriptionStatus::Cancelled;
+        // The member keeps the period already paid for, so the membership stays until
+        // its paid-through time. Only the tier seat is released now.
+        set_status(&env, &mut sub, SubscriptionStatus::Cancelled);
         write_subscription(&env, &subscription_id, &sub);
+        leave_tier(&env, &sub);
 
-        events::subscription_cancelled(&env, subscription_id, sub.subscriber);
+        events::subscription_cancelled(&env, subscription_id, sub.payer);
 
         Ok(())
     }
 
     pub fn pause_subscription(env: Env, subscription_id: String) -> Result<(), Error> {
         let mut sub = read_subscription(&env, &subscription_id).ok_or(Error::SubscriptionNotFound)?;
-        sub.subscriber.require_auth();
+        sub.payer.require_auth();
 
         // FIX: Require Active status before pausing
         if sub.status != SubscriptionStatus::Active {
             return Err(Error::InvalidStatus);
         }
 
-        sub.status = SubscriptionStatus::Paused;
+        set_status(&env, &mut sub, SubscriptionStatus::Paused);
         write_subscription(&env, &subscription_id, &sub);
 
-        events::subscription_paused(&env, subscription_id, sub.subscriber);
+        events::subscription_paused(&env, subscription_id, sub.payer);
 
         Ok(())
     }
 
     pub fn resume_subscription(env: Env, subscription_id: String) -> Result<(), Error> {
         let mut sub = read_subscription(&env, &subscription_id).ok_or(Error::SubscriptionNotFound)?;
-        sub.subscriber.require_auth();
+        sub.payer.require_auth();
 
         if sub.status != SubscriptionStatus::Paused {
             return Err(Error::InvalidStatus);
         }
 
-        sub.status = SubscriptionStatus::Active;
+        // Periods that started while paused are not owed. Once the paid period is over,
+        // billing restarts with a fresh cycle from now instead of catching them up.
+        let current_time = env.ledger().timestamp();
+        if current_time >= sub.next_payment_timestamp {
+            sub.started_at = current_time;
+            sub.next_payment_timestamp = current_time;
+        }
+
+        set_status(&env, &mut sub, SubscriptionStatus::Active);
         write_subscription(&env, &subscription_id, &sub);
+        due::schedule(&env, &sub);
 
-        events::subscription_resumed(&env, subscription_id, sub.subscriber);
+        events::subscription_resumed(&env, subscription_id, sub.payer);
 
         Ok(())
     }
 
+    /// Whether `subscriber` currently holds a paid-up membership with the artist at
+    /// `min_tier` or a tier ranked above it. Paused and cancelled members keep access until
+    /// the end of the period they paid for; past-due, lapsed and expired ones have none.
+    pub fn is_member(
+        env: Env,
+        subscriber: Address,
+        artist: Address,
+        min_tier: u32,
+    ) -> Result<bool, Error> {
+        let min_rank = read_tier(&env, &artist, min_tier)
+            .ok_or(Error::TierNotFound)?
+            .terms
+            .rank;
+        Ok(current_membership(&env, &subscriber, &artist)
+            .is_some_and(|membership| membership.tier_id.is_some() && membership.rank >= min_rank))
+    }
+
+    /// The subscriber's best current membership with the artist, if any
+    pub fn membership_of(env: Env, subscriber: Address, artist: Address) -> Option<Membership> {
+        current_membership(&env, &subscriber, &artist)
+    }
+
     pub fn get_subscription(env: Env, subscription_id: String) -> Result<Subscription, Error> {
         read_subscription(&env, &subscription_id).ok_or(Error::SubscriptionNotFound)
     }
+
+    /// The most recent payments of a subscription, oldest first
+    pub fn payment_history(env: Env, subscription_id: String) -> Vec<PaymentRecord> {
+        storage::read_payments(&env, &subscription_id, MAX_HISTORY)
+    }
+
+    /// Page through an artist's subscriptions, oldest first, optionally only those with
+    /// `status`. `limit` index positions are read from `cursor`, at most `MAX_PAGE_SIZE`,
+    /// so a filtered page can come back short; the next page starts at `cursor + limit`.
+    pub fn get_artist_subscriptions(
+        env: Env,
+        artist: Address,
+        status: Option<SubscriptionStatus>,
+        cursor: u32,
+        limit: u32,
+    ) -> Vec<Subscription> {
+        storage::read_index_page(
+            &env,
+            &SubscriptionIndex::Artist(artist),
+            status,
+            cursor,
+            limit.min(MAX_PAGE_SIZE),
+        )
+    }
+
+    /// Page through the subscriptions a fan holds, including gifts they received
+    pub fn get_subscriber_subscriptions(
+        env: Env,
+        subscriber: Address,
+        status: Option<SubscriptionStatus>,
+        cursor: u32,
+        limit: u32,
+    ) -> Vec<Subscription> {
+        storage::read_index_page(
+            &env,
+            &SubscriptionIndex::Beneficiary(subscriber),
+            status,
+            cursor,
+            limit.min(MAX_PAGE_SIZE),
+        )
+    }
+
+    /// Page through the subscriptions a fan pays for, including gifts they gave
+    pub fn get_payer_subscriptions(
+        env: Env,
+        payer: Address,
+        status: Option<SubscriptionStatus>,
+        cursor: u32,
+        limit: u32,
+    ) -> Vec<Subscription> {
+        storage::read_index_page(
+            &env,
+            &SubscriptionIndex::Payer(payer),
+            status,
+            cursor,
+            limit.min(MAX_PAGE_SIZE),
+        )
+    }
+
+    /// Number of entries in a subscription index, so callers know where paging ends
+    pub fn get_subscription_count(env: Env, index: SubscriptionIndex) -> u32 {
+        storage::index_len(&env, &index)
+    }
+
+    /// Number of the artist's subscriptions that are currently active
+    pub fn active_subscriber_count(env: Env, artist: Address) -> u32 {
+        storage::read_active_count(&env, &artist)
+    }
+}
+
+/// A subscription starting now, before any tier offer or first payment is applied
+fn new_subscription(
+    env: &Env,
+    payer: Address,
+    beneficiary: Address,
+    artist: Address,
+    token: Address,
+    amount: i128,
+    frequency: SubscriptionFrequency,
+) -> Subscription {
+    let current_time = env.ledger().timestamp();
+    Subscription {
+        id: String::from_str(env, ""),
+        payer,
+        beneficiary,
+        artist,
+        token,
+        amount,
+        frequency,
+        status: SubscriptionStatus::Active,
+        next_payment_timestamp: current_time,
+        tier_id: None,
+        failed_attempts: 0,
+        started_at: current_time,
+        periods_paid: 0,
+        last_paid_at: current_time,
+        trial_periods_left: 0,
+        intro_periods_left: 0,
+        intro_price: 0,
+        period_price: 0,
+        credit: 0,
+        periods_left: None,
+    }
+}
+
+fn open_subscription(
+    env: &Env,
+    mut subscription: Subscription,
+    tier: Option<&Tier>,
+) -> Result<String, Error> {
+    let count_key = symbol_short!("sub_cnt");
+    let count: u32 = env.storage().instance().get(&count_key).unwrap_or(0);
+    let next_count = count + 1;
+    env.storage().instance().set(&count_key, &next_count);
+
+    let mut buffer = [0u8; 10];
+    let mut num = next_count;
+    let mut len = 0;
+    while num > 0 {
+        buffer[len] = b'0' + (num % 10) as u8;
+        num /= 10;
+        len += 1;
+    }
+    for i in 0..(len / 2) {
+        buffer.swap(i, len - 1 - i);
+    }
+    let sub_id = String::from_bytes(env, &buffer[..len]);
+
+    // One trial per member and artist; later subscriptions pay full price from the start
+    let offer = tier
+        .map(|tier| &tier.terms)
+        .filter(|terms| terms.trial_periods > 0 || terms.intro_periods > 0)
+        .filter(|_| !storage::has_used_trial(env, &subscription.beneficiary, &subscription.artist));
+    if offer.is_some() {
+        storage::mark_trial_used(env, &subscription.beneficiary, &subscription.artist);
+    }
+
+    subscription.id = sub_id.clone();
+    subscription.tier_id = tier.map(|tier| tier.tier_id);
+    subscription.trial_periods_left = offer.map_or(0, |terms| terms.trial_periods);
+    subscription.intro_periods_left = offer.map_or(0, |terms| terms.intro_periods);
+    subscription.intro_price = offer.map_or(0, |terms| terms.intro_price);
+    subscription.next_payment_timestamp =
+        cycles::next_period_start(&subscription, subscription.started_at);
+
+    // Billing is in advance: the first period is charged now rather than when it ends,
+    // which also checks the allowance is in place. Nothing is charged during a free trial.
+    let (first_payment, free, intro) = cycles::price_periods(&subscription, 1);
+    if first_payment > 0 && !pull_payment(env, &subscription, first_payment) {
+        return Err(Error::PaymentFailed);
+    }
+    subscription.trial_periods_left -= free;
+    subscription.intro_periods_left -= intro;
+    subscription.periods_paid = 1 - free;
+    subscription.period_price = first_payment;
+    subscription.periods_left = subscription.periods_left.map(|left| left - 1);
+
+    write_subscription(env, &sub_id, &subscription);
+    due::schedule(env, &subscription);
+    storage::index_subscription(env, &subscription);
+    let active = storage::read_active_count(env, &subscription.artist);
+    storage::write_active_count(env, &subscription.artist, active + 1);
+    storage::add_membership(
+        env,
+        &subscription.beneficiary,
+        &subscription.artist,
+        &sub_id,
+    );
+
+    events::subscription_created(
+        env,
+        sub_id.clone(),
+        subscription.payer.clone(),
+        subscription.beneficiary.clone(),
+    );
+    if free > 0 {
+        let trial_periods = offer.map_or(0, |terms| terms.trial_periods);
+        events::trial_started(
+            env,
+            sub_id.clone(),
+            subscription.beneficiary.clone(),
+            trial_periods,
+        );
+    }
+    if first_payment > 0 {
+        record_payment(env, &subscription, first_payment, 1);
+    }
+
+    Ok(sub_id)
+}
+
+/// Charge, retry or lapse a subscription, then file it under its next due day
+/// Returns the outcome along with the amount pulled from the payer
+fn settle(
+    env: &Env,
+    subscription_id: String,
+    mut sub: Subscription,
+) -> Result<(PaymentOutcome, i128), Error> {
+    let policy = storage::read_billing_policy(env, &sub.artist);
+    let current_time = env.ledger().timestamp();
+
+    match sub.status {
+        SubscriptionStatus::Active => {
+            if current_time < sub.next_payment_timestamp {
+                return Err(Error::PaymentTooEarly);
+            }
+        }
+        SubscriptionStatus::PastDue => {
+            let lapses_at = sub
+                .next_payment_timestamp
+                .saturating_add(policy.grace_period);
+            if current_time >= lapses_at {
+                set_status(env, &mut sub, SubscriptionStatus::Cancelled);
+                write_subscription(env, &subscription_id, &sub);
+                close_subscription(env, &sub);
+
+                events::subscription_lapsed(env, subscription_id, sub.beneficiary);
+
+                return Ok((PaymentOutcome::Lapsed, 0));
+            }
+            if sub.failed_attempts > policy.max_retries {
+                return Err(Error::RetriesExhausted);
+            }
+            if current_time < due::next_retry_at(&sub, &policy) {
+                return Err(Error::PaymentTooEarly);
+            }
+        }
+        _ => return Err(Error::InvalidStatus),
+    }
+
+    // A fixed-term gift ends when the last period it paid for runs out
+    if sub.periods_left == Some(0) {
+        set_status(env, &mut sub, SubscriptionStatus::Expired);
+        write_subscription(env, &subscription_id, &sub);
+        close_subscription(env, &sub);
+
+        events::subscription_expired(env, subscription_id, sub.beneficiary);
+
+        return Ok((PaymentOutcome::Expired, 0));
+    }
+
+    // Tier changes made during the last cycle take effect from this one
+    if let Some(tier) = sub.tier_id.and_then(|id| read_tier(env, &sub.artist, id)) {
+        if tier.terms.frequency != sub.frequency {
+            sub.started_at = sub.next_payment_timestamp;
+        }
+        sub.amount = tier.terms.price;
+        sub.frequency = tier.terms.frequency;
+    }
+
+    // Cycles stay anchored to the start time. Periods missed beyond the catch-up limit
+    // are skipped rather than charged.
+    let (due_periods, following_period) = cycles::elapsed_periods(&sub, current_time);
+    let charged_periods = due_periods
+        .min(policy.max_catch_up_periods as u64 + 1)
+        .min(sub.periods_left.map_or(u64::MAX, u64::from));
+    let (price, free, intro) = cycles::price_periods(&sub, charged_periods as u32);
+    let period_price = price - cycles::price_periods(&sub, charged_periods as u32 - 1).0;
+    // Only trials start with no paid periods, so the first charge after one is the conversion
+    let converted = sub.periods_paid == 0 && price > 0;
+
+    let credit_used = sub.credit.min(price);
+    let amount = price - credit_used;
+    if amount > 0 && !pull_payment(env, &sub, amount) {
+        set_status(env, &mut sub, SubscriptionStatus::PastDue);
+        sub.failed_attempts += 1;
+        write_subscription(env, &subscription_id, &sub);
+        due::schedule(env, &sub);
+
+        events::payment_failed(env, subscription_id, sub.failed_attempts);
+
+        return Ok((PaymentOutcome::PastDue, 0));
+    }
+
+    set_status(env, &mut sub, SubscriptionStatus::Active);
+    sub.failed_attempts = 0;
+    sub.next_payment_timestamp = following_period;
+    sub.periods_paid += charged_periods as u32 - free;
+    sub.trial_periods_left -= free;
+    sub.intro_periods_left -= intro;
+    sub.period_price = period_price;
+    sub.credit -= credit_used;
+    sub.periods_left = sub.periods_left.map(|left| left - charged_periods as u32);
+    sub.last_paid_at = current_time;
+
+    write_subscription(env, &subscription_id, &sub);
+    due::schedule(env, &sub);
+    if price > 0 {
+        record_payment(env, &sub, amount, charged_periods as u32 - free);
+    }
+    if converted {
+        events::trial_converted(env, subscription_id, amount);
+    }
+
+    Ok((PaymentOutcome::Paid, amount))
+}
+
+/// Tip an approved keeper for each payment collected, up to what is left in the pool. Each
+/// tip is capped at a share of its payment, so settling small payments cannot drain the pool.
+fn pay_keeper(env: &Env, keeper: &Address, collected: &Vec<i128>) {
+    if !storage::is_keeper_approved(env, keeper) {
+        return;
+    }
+    let Some(config) = storage::read_keeper_config(env) else {
+        return;
+    };
+    let earned: i128 = collected
+        .iter()
+        .map(|amount| {
+            let cap = amount * MAX_KEEPER_SHARE_BPS / 10_000;
+            config.tip_per_item.min(cap)
+        })
+        .sum();
+    let pool = storage::read_keeper_pool(env);
+    let amount = earned.min(pool);
+    if amount <= 0 {
+        return;
+    }
+
+    token::Client::new(env, &config.token).transfer(
+        &env.current_contract_address(),
+        keeper,
+        &amount,
+    );
+    storage::write_keeper_pool(env, pool - amount);
+
+    events::keeper_paid(env, keeper.clone(), collected.len(), amount);
+}
+
+/// Pull a payment from the payer's allowance to the artist.
+/// Returns false instead of failing when the allowance or balance falls short.
+fn pull_payment(env: &Env, sub: &Subscription, amount: i128) -> bool {
+    let token_client = token::Client::new(env, &sub.token);
+    matches!(
+        token_client.try_transfer_from(
+            &env.current_contract_address(),
+            &sub.payer,
+            &sub.artist,
+            &amount,
+        ),
+        Ok(Ok(()))
+    )
+}
+
+fn record_payment(env: &Env, sub: &Subscription, amount: i128, periods: u32) {
+    storage::append_payment(
+        env,
+        &sub.id,
+        &PaymentRecord {
+            amount,
+            periods,
+            paid_at: sub.last_paid_at,
+            paid_through: sub.next_payment_timestamp,
+        },
+    );
+
+    events::payment_processed(env, sub.id.clone(), amount);
+}
+
+/// The highest-ranked membership among the beneficiary's subscriptions with the artist
+/// that is paid up right now
+fn current_membership(env: &Env, beneficiary: &Address, artist: &Address) -> Option<Membership> {
+    let current_time = env.ledger().timestamp();
+    let mut best: Option<Membership> = None;
+
+    for id in storage::read_memberships(env, beneficiary, artist).iter() {
+        let Some(sub) = read_subscription(env, &id) else {
+            continue;
+        };
+        // Lapsed subscriptions are cancelled too, but they leave the index when they lapse
+        let in_good_standing = matches!(
+            sub.status,
+            SubscriptionStatus::Active | SubscriptionStatus::Paused | SubscriptionStatus::Cancelled
+        );
+        if !in_good_standing || current_time >= sub.next_payment_timestamp {
+            continue;
+        }
+
+        let rank = sub
+            .tier_id
+            .and_then(|tier_id| read_tier(env, artist, tier_id))
+            .map_or(0, |tier| tier.terms.rank);
+        // Tier memberships win ties with free-form subscriptions
+        let key = (rank, sub.tier_id.is_some());
+        if best
+            .as_ref()
+            .is_some_and(|best| (best.rank, best.tier_id.is_some()) >= key)
+        {
+            continue;
+        }
+        best = Some(Membership {
+            subscription_id: id,
+            tier_id: sub.tier_id,
+            rank,
+            status: sub.status,
+            paid_through: sub.next_payment_timestamp,
+        });
+    }
+    best
+}
+
+/// Move a subscription to a new status, keeping its artist's active count in step.
+/// Every status change after creation goes through here.
+fn set_status(env: &Env, sub: &mut Subscription, status: SubscriptionStatus) {
+    let was_active = sub.status == SubscriptionStatus::Active;
+    let is_active = status == SubscriptionStatus::Active;
+    if was_active != is_active {
+        let count = storage::read_active_count(env, &sub.artist);
+        let count = if is_active {
+            count + 1
+        } else {
+            count.saturating_sub(1)
+        };
+        storage::write_active_count(env, &sub.artist, count);
+    }
+    sub.status = status;
+}
+
+/// Release what a lapsed or expired subscription held
+fn close_subscription(env: &Env, sub: &Subscription) {
+    leave_tier(env, sub);
+    storage::remove_membership(env, &sub.beneficiary, &sub.artist, &sub.id);
+}
+
+/// Take a seat at an active tier that still has room
+fn join_tier(env: &Env, artist: &Address, tier_id: u32) -> Result<Tier, Error> {
+    let mut tier = read_tier(env, artist, tier_id).ok_or(Error::TierNotFound)?;
+    if !tier.is_active {
+        return Err(Error::TierInactive);
+    }
+    if tier
+        .terms
+        .max_subscribers
+        .is_some_and(|max| tier.current_subscribers >= max)
+    {
+        return Err(Error::TierFull);
+    }
+
+    tier.current_subscribers += 1;
+    write_tier(env, &tier);
+    Ok(tier)
+}
+
+/// Free a subscription's tier seat for the next subscriber
+fn leave_tier(env: &Env, sub: &Subscription) {
+    if let Some(mut tier) = sub.tier_id.and_then(|id| read_tier(env, &sub.artist, id)) {
+        tier.current_subscribers = tier.current_subscribers.saturating_sub(1);
+        write_tier(env, &tier);
+    }
+}
+
+fn check_frequency(
+    env: &Env,
+    artist: &Address,
+    frequency: &SubscriptionFrequency,
+) -> Result<(), Error> {
+    let bounds = storage::read_interval_bounds(env, artist);
+    let interval = cycles::nominal_duration(frequency);
+    if interval < bounds.min_interval || interval > bounds.max_interval {
+        return Err(Error::InvalidFrequency);
+    }
+    Ok(())
+}
+
+fn validate_tier_terms(terms: &TierTerms) -> Result<(), Error> {
+    if terms.price <= 0 || terms.max_subscribers == Some(0) {
+        return Err(Error::InvalidTier);
+    }
+    // An intro offer needs a discounted price; a free period is what trials are for
+    if terms.intro_periods > 0 && (terms.intro_price <= 0 || terms.intro_price >= terms.price) {
+        return Err(Error::InvalidTier);
+    }
+    Ok(())
 }
 
 #[cfg(test)]
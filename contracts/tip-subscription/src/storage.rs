@@ -1,15 +1,45 @@
-use soroban_sdk::{contracttype, Env, String};
-use crate::types::Subscription;
+use soroban_sdk::{contracttype, Address, Env, String, Vec};
+use crate::types::{
+    BillingPolicy, IntervalBounds, KeeperConfig, PaymentRecord, Subscription, SubscriptionIndex,
+    SubscriptionStatus, Tier,
+};
 
 #[contracttype]
 #[derive(Clone)]
 pub enum DataKey {
     Subscription(String),
+    Tier(Address, u32), // artist + tier id
+    TierCount(Address),
+    BillingPolicy(Address),
+    Admin,
+    KeeperConfig,
+    KeeperPool,
+    Keeper(Address),    // keepers the admin approved for pool tips
+    DueLen(u64),        // day number -> entries filed under that day
+    DueEntry(u64, u32), // day number + position -> subscription id
+    DueHead(u64),       // first position of a day that has not been handled
+    DueFiled(String),   // day a subscription is currently filed under
+    DueCursor,          // earliest day that may still hold due subscriptions
+    PaymentCount(String),
+    Payment(String, u32), // subscription id + position
+    IntervalBounds(Address),
+    TrialUsed(Address, Address),  // subscriber + artist
+    Membership(Address, Address), // beneficiary + artist -> ids of their open subscriptions
+    IndexLen(SubscriptionIndex),
+    IndexEntry(SubscriptionIndex, u32), // index + position -> subscription id
+    BeneficiaryEntry(String),           // position of the current beneficiary's index entry
+    ActiveCount(Address),
 }
 
 const LIFETIME_THRESHOLD: u32 = 100_000; // ~160 days at 5s/ledger
 const EXTEND_TO: u32 = 200_000;
 
+const DEFAULT_GRACE_PERIOD: u64 = 259_200; // 3 days
+const DEFAULT_MAX_RETRIES: u32 = 2;
+const DEFAULT_MAX_CATCH_UP_PERIODS: u32 = 0;
+const DEFAULT_MIN_INTERVAL: u64 = 86_400; // 1 day
+const DEFAULT_MAX_INTERVAL: u64 = 31_536_000; // 365 days
+
 pub fn write_subscription(env: &Env, id: &String, sub: &Subscription) {
     let key = DataKey::Subscription(id.clone());
     env.storage().persistent().set(&key, sub);
@@ -31,4 +61,418 @@ pub fn read_subscription(env: &Env, id: &String) -> Option<Subscription> {
 
 pub fn remove_subscription(env: &Env, id: &String) {
     env.storage().persistent().remove(&DataKey::Subscription(id.clone()));
+}
+
+pub fn next_tier_id(env: &Env, artist: &Address) -> u32 {
+    let tier_id = tier_count(env, artist) + 1;
+    let key = DataKey::TierCount(artist.clone());
+    env.storage().persistent().set(&key, &tier_id);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+    tier_id
+}
+
+pub fn tier_count(env: &Env, artist: &Address) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TierCount(artist.clone()))
+        .unwrap_or(0)
+}
+
+pub fn write_tier(env: &Env, tier: &Tier) {
+    let key = DataKey::Tier(tier.artist.clone(), tier.tier_id);
+    env.storage().persistent().set(&key, tier);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+}
+
+pub fn read_tier(env: &Env, artist: &Address, tier_id: u32) -> Option<Tier> {
+    let key = DataKey::Tier(artist.clone(), tier_id);
+    let tier = env.storage().persistent().get(&key);
+    if tier.is_some() {
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+    }
+    tier
+}
+
+pub fn read_tiers(env: &Env, artist: &Address) -> Vec<Tier> {
+    let mut tiers = Vec::new(env);
+    for tier_id in 1..=tier_count(env, artist) {
+        if let Some(tier) = read_tier(env, artist, tier_id) {
+            tiers.push_back(tier);
+        }
+    }
+    tiers
+}
+
+pub fn write_billing_policy(env: &Env, artist: &Address, policy: &BillingPolicy) {
+    let key = DataKey::BillingPolicy(artist.clone());
+    env.storage().persistent().set(&key, policy);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+}
+
+pub fn read_billing_policy(env: &Env, artist: &Address) -> BillingPolicy {
+    env.storage()
+        .persistent()
+        .get(&DataKey::BillingPolicy(artist.clone()))
+        .unwrap_or(BillingPolicy {
+            grace_period: DEFAULT_GRACE_PERIOD,
+            max_retries: DEFAULT_MAX_RETRIES,
+            max_catch_up_periods: DEFAULT_MAX_CATCH_UP_PERIODS,
+        })
+}
+
+pub fn has_admin(env: &Env) -> bool {
+    env.storage().instance().has(&DataKey::Admin)
+}
+
+pub fn write_admin(env: &Env, admin: &Address) {
+    env.storage().instance().set(&DataKey::Admin, admin);
+}
+
+pub fn read_admin(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&DataKey::Admin)
+}
+
+pub fn write_keeper_config(env: &Env, config: &KeeperConfig) {
+    env.storage().instance().set(&DataKey::KeeperConfig, config);
+}
+
+pub fn read_keeper_config(env: &Env) -> Option<KeeperConfig> {
+    env.storage().instance().get(&DataKey::KeeperConfig)
+}
+
+pub fn write_keeper_pool(env: &Env, balance: i128) {
+    env.storage().instance().set(&DataKey::KeeperPool, &balance);
+}
+
+pub fn read_keeper_pool(env: &Env) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::KeeperPool)
+        .unwrap_or(0)
+}
+
+pub fn write_keeper_approved(env: &Env, keeper: &Address, approved: bool) {
+    let key = DataKey::Keeper(keeper.clone());
+    if approved {
+        env.storage().persistent().set(&key, &true);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+    } else {
+        env.storage().persistent().remove(&key);
+    }
+}
+
+pub fn is_keeper_approved(env: &Env, keeper: &Address) -> bool {
+    env.storage()
+        .persistent()
+        .has(&DataKey::Keeper(keeper.clone()))
+}
+
+/// File a subscription under a day. An entry left under the day it was filed under
+/// before is skipped when read.
+pub fn file_due(env: &Env, bucket: u64, id: &String) {
+    let len = due_len(env, bucket);
+
+    let key = DataKey::DueEntry(bucket, len);
+    env.storage().persistent().set(&key, id);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+
+    let key = DataKey::DueLen(bucket);
+    env.storage().persistent().set(&key, &(len + 1));
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+
+    let key = DataKey::DueFiled(id.clone());
+    env.storage().persistent().set(&key, &bucket);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+}
+
+pub fn due_len(env: &Env, bucket: u64) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::DueLen(bucket))
+        .unwrap_or(0)
+}
+
+pub fn read_due_entry(env: &Env, bucket: u64, position: u32) -> Option<String> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::DueEntry(bucket, position))
+}
+
+pub fn remove_due_entry(env: &Env, bucket: u64, position: u32) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::DueEntry(bucket, position));
+}
+
+pub fn read_due_head(env: &Env, bucket: u64) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::DueHead(bucket))
+        .unwrap_or(0)
+}
+
+pub fn write_due_head(env: &Env, bucket: u64, head: u32) {
+    let key = DataKey::DueHead(bucket);
+    env.storage().persistent().set(&key, &head);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+}
+
+/// Forget a day once every entry filed under it has been handled
+pub fn remove_due_bucket(env: &Env, bucket: u64) {
+    env.storage().persistent().remove(&DataKey::DueLen(bucket));
+    env.storage().persistent().remove(&DataKey::DueHead(bucket));
+}
+
+pub fn read_due_filed(env: &Env, id: &String) -> Option<u64> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::DueFiled(id.clone()))
+}
+
+pub fn remove_due_filed(env: &Env, id: &String) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::DueFiled(id.clone()));
+}
+
+pub fn write_due_cursor(env: &Env, bucket: u64) {
+    env.storage().instance().set(&DataKey::DueCursor, &bucket);
+}
+
+pub fn read_due_cursor(env: &Env) -> Option<u64> {
+    env.storage().instance().get(&DataKey::DueCursor)
+}
+
+pub fn append_payment(env: &Env, id: &String, payment: &PaymentRecord) {
+    let count = payment_count(env, id);
+
+    let key = DataKey::Payment(id.clone(), count);
+    env.storage().persistent().set(&key, payment);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+
+    let key = DataKey::PaymentCount(id.clone());
+    env.storage().persistent().set(&key, &(count + 1));
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+}
+
+pub fn payment_count(env: &Env, id: &String) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PaymentCount(id.clone()))
+        .unwrap_or(0)
+}
+
+/// Up to `limit` of the latest payments, oldest first
+pub fn read_payments(env: &Env, id: &String, limit: u32) -> Vec<PaymentRecord> {
+    let mut payments = Vec::new(env);
+    let count = payment_count(env, id);
+    for position in count.saturating_sub(limit)..count {
+        if let Some(payment) = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Payment(id.clone(), position))
+        {
+            payments.push_back(payment);
+        }
+    }
+    payments
+}
+
+pub fn write_interval_bounds(env: &Env, artist: &Address, bounds: &IntervalBounds) {
+    let key = DataKey::IntervalBounds(artist.clone());
+    env.storage().persistent().set(&key, bounds);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+}
+
+pub fn read_interval_bounds(env: &Env, artist: &Address) -> IntervalBounds {
+    env.storage()
+        .persistent()
+        .get(&DataKey::IntervalBounds(artist.clone()))
+        .unwrap_or(IntervalBounds {
+            min_interval: DEFAULT_MIN_INTERVAL,
+            max_interval: DEFAULT_MAX_INTERVAL,
+        })
+}
+
+pub fn mark_trial_used(env: &Env, subscriber: &Address, artist: &Address) {
+    let key = DataKey::TrialUsed(subscriber.clone(), artist.clone());
+    env.storage().persistent().set(&key, &true);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+}
+
+pub fn has_used_trial(env: &Env, subscriber: &Address, artist: &Address) -> bool {
+    env.storage()
+        .persistent()
+        .has(&DataKey::TrialUsed(subscriber.clone(), artist.clone()))
+}
+
+pub fn read_memberships(env: &Env, beneficiary: &Address, artist: &Address) -> Vec<String> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Membership(beneficiary.clone(), artist.clone()))
+        .unwrap_or(Vec::new(env))
+}
+
+/// Add a subscription to a member's index, dropping cancelled ones whose paid time is over
+pub fn add_membership(env: &Env, beneficiary: &Address, artist: &Address, id: &String) {
+    let now = env.ledger().timestamp();
+    let mut ids = Vec::new(env);
+    for existing in read_memberships(env, beneficiary, artist).iter() {
+        let ended = read_subscription(env, &existing).is_none_or(|sub| {
+            sub.status == SubscriptionStatus::Cancelled && now >= sub.next_payment_timestamp
+        });
+        if !ended {
+            ids.push_back(existing);
+        }
+    }
+    ids.push_back(id.clone());
+    write_memberships(env, beneficiary, artist, &ids);
+}
+
+pub fn remove_membership(env: &Env, beneficiary: &Address, artist: &Address, id: &String) {
+    let mut ids = read_memberships(env, beneficiary, artist);
+    if let Some(index) = ids.first_index_of(id) {
+        ids.remove(index);
+    }
+    write_memberships(env, beneficiary, artist, &ids);
+}
+
+fn write_memberships(env: &Env, beneficiary: &Address, artist: &Address, ids: &Vec<String>) {
+    let key = DataKey::Membership(beneficiary.clone(), artist.clone());
+    if ids.is_empty() {
+        env.storage().persistent().remove(&key);
+        return;
+    }
+    env.storage().persistent().set(&key, ids);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+}
+
+pub fn index_subscription(env: &Env, sub: &Subscription) {
+    append_to_index(env, SubscriptionIndex::Artist(sub.artist.clone()), &sub.id);
+    append_to_index(env, SubscriptionIndex::Payer(sub.payer.clone()), &sub.id);
+    index_beneficiary(env, sub);
+}
+
+/// Add the subscription to its beneficiary's index. Entries left behind in the index of an
+/// earlier beneficiary are skipped when read.
+pub fn index_beneficiary(env: &Env, sub: &Subscription) {
+    let position = append_to_index(
+        env,
+        SubscriptionIndex::Beneficiary(sub.beneficiary.clone()),
+        &sub.id,
+    );
+
+    let key = DataKey::BeneficiaryEntry(sub.id.clone());
+    env.storage().persistent().set(&key, &position);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+}
+
+fn append_to_index(env: &Env, index: SubscriptionIndex, id: &String) -> u32 {
+    let len = index_len(env, &index);
+
+    let key = DataKey::IndexEntry(index.clone(), len);
+    env.storage().persistent().set(&key, id);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+
+    let key = DataKey::IndexLen(index);
+    env.storage().persistent().set(&key, &(len + 1));
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
+
+    len
+}
+
+pub fn index_len(env: &Env, index: &SubscriptionIndex) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::IndexLen(index.clone()))
+        .unwrap_or(0)
+}
+
+/// Read the subscriptions at positions `cursor` to `cursor + limit` of an index, oldest
+/// first, keeping only those with `status` when one is given
+pub fn read_index_page(
+    env: &Env,
+    index: &SubscriptionIndex,
+    status: Option<SubscriptionStatus>,
+    cursor: u32,
+    limit: u32,
+) -> Vec<Subscription> {
+    let mut subs = Vec::new(env);
+    let end = cursor.saturating_add(limit).min(index_len(env, index));
+
+    for position in cursor..end {
+        let id: Option<String> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::IndexEntry(index.clone(), position));
+        let Some(sub) = id.and_then(|id| read_subscription(env, &id)) else {
+            continue;
+        };
+        if status.as_ref().is_some_and(|status| *status != sub.status) {
+            continue;
+        }
+        if matches!(index, SubscriptionIndex::Beneficiary(_))
+            && read_beneficiary_entry(env, &sub.id) != Some(position)
+        {
+            continue;
+        }
+        subs.push_back(sub);
+    }
+
+    subs
+}
+
+fn read_beneficiary_entry(env: &Env, id: &String) -> Option<u32> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::BeneficiaryEntry(id.clone()))
+}
+
+pub fn read_active_count(env: &Env, artist: &Address) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::ActiveCount(artist.clone()))
+        .unwrap_or(0)
+}
+
+pub fn write_active_count(env: &Env, artist: &Address, count: u32) {
+    let key = DataKey::ActiveCount(artist.clone());
+    env.storage().persistent().set(&key, &count);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, LIFETIME_THRESHOLD, EXTEND_TO);
 }
\ No newline at end of file
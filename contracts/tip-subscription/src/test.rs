@@ -3,7 +3,7 @@
 use super::*;
 use soroban_sdk::{
     testutils::{Address as _, Ledger},
-    token, Address, Env,
+    token, vec, Address, BytesN, Env, String,
 };
 
 #[allow(deprecated)]
@@ -33,6 +33,8 @@ fn setup_test() -> (
     let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
 
     token_admin_client.mint(&subscriber, &10_000);
+    // Payments are pulled from the allowance granted to the subscription contract
+    token_client.approve(&subscriber, &contract_id, &10_000, &100_000);
 
     (
         env,
@@ -61,6 +63,9 @@ fn test_create_and_process_subscription() {
     let sub = client.get_subscription(&sub_id);
     assert_eq!(sub.status, SubscriptionStatus::Active);
     assert_eq!(sub.amount, amount);
+    // Billing is in advance, so the first week is charged on creation
+    assert_eq!(token_client.balance(&artist), amount);
+    assert_eq!(sub.next_payment_timestamp, WEEK_IN_SECONDS);
 
     env.ledger().with_mut(|li| {
         li.timestamp = WEEK_IN_SECONDS + 1;
@@ -115,4 +120,940 @@ fn test_lifecycle_pause_resume_cancel() {
 
     client.cancel_subscription(&sub_id);
     assert_eq!(client.get_subscription(&sub_id).status, SubscriptionStatus::Cancelled);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_resume_does_not_bill_paused_periods() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+    client.set_billing_policy(
+        &artist,
+        &BillingPolicy {
+            grace_period: 259_200,
+            max_retries: 2,
+            max_catch_up_periods: 10,
+        },
+    );
+
+    let sub_id = client.create_subscription(
+        &subscriber,
+        &artist,
+        &token_client.address,
+        &100,
+        &SubscriptionFrequency::Weekly,
+    );
+    env.ledger().with_mut(|li| li.timestamp = DAY_IN_SECONDS);
+    client.pause_subscription(&sub_id);
+
+    // Paused for five weeks, then only the new period is charged
+    let resumed_at = 5 * WEEK_IN_SECONDS + DAY_IN_SECONDS;
+    env.ledger().with_mut(|li| li.timestamp = resumed_at);
+    client.resume_subscription(&sub_id);
+    assert_eq!(client.process_payment(&sub_id), PaymentOutcome::Paid);
+    assert_eq!(token_client.balance(&artist), 200);
+
+    let sub = client.get_subscription(&sub_id);
+    assert_eq!(sub.periods_paid, 2);
+    assert_eq!(sub.next_payment_timestamp, resumed_at + WEEK_IN_SECONDS);
+}
+
+fn weekly_tier(env: &Env, token: &Address, price: i128, max_subscribers: Option<u32>) -> TierTerms {
+    TierTerms {
+        token: token.clone(),
+        price,
+        frequency: SubscriptionFrequency::Weekly,
+        max_subscribers,
+        perks_hash: BytesN::from_array(env, &[7; 32]),
+        rank: 1,
+        trial_periods: 0,
+        intro_periods: 0,
+        intro_price: 0,
+    }
+}
+
+#[test]
+fn test_tier_cap_and_active_flag() {
+    let (env, client, subscriber, artist, token_client, token_admin) = setup_test();
+    let other_fan = Address::generate(&env);
+    token_admin.mint(&other_fan, &10_000);
+
+    let tier_id = client.create_tier(
+        &artist,
+        &weekly_tier(&env, &token_client.address, 200, Some(1)),
+    );
+    assert_eq!(tier_id, 1);
+
+    let sub_id = client.subscribe(&subscriber, &artist, &tier_id);
+    let sub = client.get_subscription(&sub_id);
+    assert_eq!(sub.tier_id, Some(tier_id));
+    assert_eq!(sub.amount, 200);
+    assert_eq!(client.get_tier(&artist, &tier_id).current_subscribers, 1);
+
+    assert_eq!(
+        client.try_subscribe(&other_fan, &artist, &tier_id),
+        Err(Ok(Error::TierFull))
+    );
+
+    // Cancelling frees the seat
+    client.cancel_subscription(&sub_id);
+    assert_eq!(client.get_tier(&artist, &tier_id).current_subscribers, 0);
+
+    client.set_tier_active(&artist, &tier_id, &false);
+    assert_eq!(
+        client.try_subscribe(&other_fan, &artist, &tier_id),
+        Err(Ok(Error::TierInactive))
+    );
+    assert_eq!(
+        client.try_subscribe(&other_fan, &artist, &2),
+        Err(Ok(Error::TierNotFound))
+    );
+    assert_eq!(
+        client.try_create_tier(&artist, &weekly_tier(&env, &token_client.address, 0, None)),
+        Err(Ok(Error::InvalidTier))
+    );
+    assert_eq!(client.get_tiers(&artist).len(), 1);
+}
+
+#[test]
+fn test_tier_price_change_applies_next_cycle() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+
+    let tier_id = client.create_tier(
+        &artist,
+        &weekly_tier(&env, &token_client.address, 200, None),
+    );
+    let sub_id = client.subscribe(&subscriber, &artist, &tier_id);
+
+    assert_eq!(token_client.balance(&artist), 200);
+
+    // Nothing is charged for the cycle in progress
+    client.update_tier(
+        &artist,
+        &tier_id,
+        &weekly_tier(&env, &token_client.address, 300, None),
+    );
+    assert_eq!(token_client.balance(&artist), 200);
+
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    client.process_payment(&sub_id);
+    assert_eq!(client.get_subscription(&sub_id).amount, 300);
+    assert_eq!(token_client.balance(&artist), 500);
+}
+
+#[test]
+fn test_failed_pull_goes_past_due_and_recovers() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+
+    // The first period is charged at creation
+    let sub_id = client.create_subscription(
+        &subscriber,
+        &artist,
+        &token_client.address,
+        &100,
+        &SubscriptionFrequency::Weekly,
+    );
+    assert_eq!(token_client.balance(&artist), 100);
+
+    token_client.approve(&subscriber, &client.address, &0, &100_000);
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    assert_eq!(client.process_payment(&sub_id), PaymentOutcome::PastDue);
+
+    let sub = client.get_subscription(&sub_id);
+    assert_eq!(sub.status, SubscriptionStatus::PastDue);
+    assert_eq!(sub.failed_attempts, 1);
+
+    // Default policy: 3 day grace period with 2 retries, one per day
+    token_client.approve(&subscriber, &client.address, &1_000, &100_000);
+    assert_eq!(
+        client.try_process_payment(&sub_id),
+        Err(Ok(Error::PaymentTooEarly))
+    );
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = WEEK_IN_SECONDS + 86_400);
+    assert_eq!(client.process_payment(&sub_id), PaymentOutcome::Paid);
+    assert_eq!(token_client.balance(&artist), 200);
+
+    let sub = client.get_subscription(&sub_id);
+    assert_eq!(sub.status, SubscriptionStatus::Active);
+    assert_eq!(sub.failed_attempts, 0);
+}
+
+#[test]
+fn test_past_due_subscription_lapses_after_grace_period() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+
+    token_client.approve(&subscriber, &client.address, &0, &100_000);
+    assert_eq!(
+        client.try_create_subscription(
+            &subscriber,
+            &artist,
+            &token_client.address,
+            &100,
+            &SubscriptionFrequency::Weekly,
+        ),
+        Err(Ok(Error::PaymentFailed))
+    );
+
+    token_client.approve(&subscriber, &client.address, &100, &100_000);
+    let sub_id = client.create_subscription(
+        &subscriber,
+        &artist,
+        &token_client.address,
+        &100,
+        &SubscriptionFrequency::Weekly,
+    );
+    // Policies that could overflow the retry schedule are refused
+    for policy in [
+        BillingPolicy {
+            grace_period: u64::MAX,
+            max_retries: 1,
+            max_catch_up_periods: 0,
+        },
+        BillingPolicy {
+            grace_period: 86_400,
+            max_retries: u32::MAX,
+            max_catch_up_periods: 0,
+        },
+        BillingPolicy {
+            grace_period: 86_400,
+            max_retries: 1,
+            max_catch_up_periods: u32::MAX,
+        },
+    ] {
+        assert_eq!(
+            client.try_set_billing_policy(&artist, &policy),
+            Err(Ok(Error::InvalidBillingPolicy))
+        );
+    }
+    client.set_billing_policy(
+        &artist,
+        &BillingPolicy {
+            grace_period: 86_400,
+            max_retries: 1,
+            max_catch_up_periods: 0,
+        },
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    assert_eq!(client.process_payment(&sub_id), PaymentOutcome::PastDue);
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = WEEK_IN_SECONDS + 43_200);
+    assert_eq!(client.process_payment(&sub_id), PaymentOutcome::PastDue);
+    assert_eq!(
+        client.try_process_payment(&sub_id),
+        Err(Ok(Error::RetriesExhausted))
+    );
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = WEEK_IN_SECONDS + 86_400);
+    assert_eq!(client.process_payment(&sub_id), PaymentOutcome::Lapsed);
+    assert_eq!(
+        client.get_subscription(&sub_id).status,
+        SubscriptionStatus::Cancelled
+    );
+}
+#[test]
+fn test_late_payment_catches_up_anchored_periods() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+
+    let sub_id = client.create_subscription(
+        &subscriber,
+        &artist,
+        &token_client.address,
+        &100,
+        &SubscriptionFrequency::Weekly,
+    );
+    client.set_billing_policy(
+        &artist,
+        &BillingPolicy {
+            grace_period: 259_200,
+            max_retries: 2,
+            max_catch_up_periods: 2,
+        },
+    );
+
+    // Three periods have started since the first was paid; two are caught up and the
+    // oldest is skipped
+    env.ledger()
+        .with_mut(|li| li.timestamp = 4 * WEEK_IN_SECONDS + 86_400);
+    client.process_payment(&sub_id);
+    assert_eq!(token_client.balance(&artist), 400);
+
+    let sub = client.get_subscription(&sub_id);
+    assert_eq!(sub.periods_paid, 4);
+    assert_eq!(sub.last_paid_at, 4 * WEEK_IN_SECONDS + 86_400);
+    assert_eq!(sub.next_payment_timestamp, 5 * WEEK_IN_SECONDS);
+
+    // The late payment does not push the schedule back
+    env.ledger()
+        .with_mut(|li| li.timestamp = 5 * WEEK_IN_SECONDS);
+    client.process_payment(&sub_id);
+    assert_eq!(
+        client.get_subscription(&sub_id).next_payment_timestamp,
+        6 * WEEK_IN_SECONDS
+    );
+
+    let history = client.payment_history(&sub_id);
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.get(0).unwrap().paid_through, WEEK_IN_SECONDS);
+    assert_eq!(history.get(1).unwrap().amount, 300);
+    assert_eq!(history.get(1).unwrap().periods, 3);
+    assert_eq!(history.get(2).unwrap().paid_at, 5 * WEEK_IN_SECONDS);
+}
+
+#[test]
+fn test_custom_frequencies_respect_artist_bounds() {
+    let (_, client, subscriber, artist, token_client, _) = setup_test();
+
+    let sub_id = client.create_subscription(
+        &subscriber,
+        &artist,
+        &token_client.address,
+        &10,
+        &SubscriptionFrequency::Daily,
+    );
+    assert_eq!(
+        client.get_subscription(&sub_id).next_payment_timestamp,
+        DAY_IN_SECONDS
+    );
+
+    let three_days = SubscriptionFrequency::Custom(3 * DAY_IN_SECONDS);
+    let sub_id = client.create_subscription(
+        &subscriber,
+        &artist,
+        &token_client.address,
+        &10,
+        &three_days,
+    );
+    assert_eq!(
+        client.get_subscription(&sub_id).next_payment_timestamp,
+        3 * DAY_IN_SECONDS
+    );
+
+    client.set_interval_bounds(
+        &artist,
+        &IntervalBounds {
+            min_interval: WEEK_IN_SECONDS,
+            max_interval: MONTH_IN_SECONDS,
+        },
+    );
+    for frequency in [
+        three_days,
+        SubscriptionFrequency::Daily,
+        SubscriptionFrequency::Yearly,
+    ] {
+        assert_eq!(
+            client.try_create_subscription(
+                &subscriber,
+                &artist,
+                &token_client.address,
+                &10,
+                &frequency,
+            ),
+            Err(Ok(Error::InvalidFrequency))
+        );
+    }
+    for min_interval in [0, 60] {
+        assert_eq!(
+            client.try_set_interval_bounds(
+                &artist,
+                &IntervalBounds {
+                    min_interval,
+                    max_interval: WEEK_IN_SECONDS,
+                },
+            ),
+            Err(Ok(Error::InvalidFrequency))
+        );
+    }
+}
+
+#[test]
+fn test_calendar_monthly_renews_on_same_day() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+
+    // 2025-01-31 09:00 UTC
+    env.ledger().with_mut(|li| li.timestamp = 1_738_314_000);
+    let sub_id = client.create_subscription(
+        &subscriber,
+        &artist,
+        &token_client.address,
+        &100,
+        &SubscriptionFrequency::CalendarMonthly,
+    );
+
+    // February is short, so the renewal falls on its last day
+    let feb_28 = 1_740_733_200;
+    assert_eq!(
+        client.get_subscription(&sub_id).next_payment_timestamp,
+        feb_28
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = feb_28);
+    client.process_payment(&sub_id);
+
+    // ...and March goes back to the 31st
+    let mar_31 = 1_743_411_600;
+    assert_eq!(
+        client.get_subscription(&sub_id).next_payment_timestamp,
+        mar_31
+    );
+    assert_eq!(token_client.balance(&artist), 200);
+}
+
+fn funded_subscriber(
+    env: &Env,
+    client: &TipSubscriptionContractClient,
+    token_client: &token::Client,
+    token_admin: &token::StellarAssetClient,
+) -> Address {
+    let subscriber = Address::generate(env);
+    token_admin.mint(&subscriber, &10_000);
+    token_client.approve(&subscriber, &client.address, &10_000, &100_000);
+    subscriber
+}
+
+#[test]
+fn test_process_due_settles_only_due_subscriptions() {
+    let (env, client, subscriber, artist, token_client, token_admin) = setup_test();
+    let keeper = Address::generate(&env);
+
+    let second = funded_subscriber(&env, &client, &token_client, &token_admin);
+    let monthly = funded_subscriber(&env, &client, &token_client, &token_admin);
+    for (fan, frequency) in [
+        (&subscriber, SubscriptionFrequency::Weekly),
+        (&second, SubscriptionFrequency::Weekly),
+        (&monthly, SubscriptionFrequency::Monthly),
+    ] {
+        client.create_subscription(fan, &artist, &token_client.address, &100, &frequency);
+    }
+    assert_eq!(token_client.balance(&artist), 300);
+    assert_eq!(client.process_due(&keeper, &10).len(), 0);
+
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    let first_batch = client.process_due(&keeper, &1);
+    assert_eq!(first_batch.len(), 1);
+    assert_eq!(first_batch.get(0).unwrap().outcome, PaymentOutcome::Paid);
+
+    let second_batch = client.process_due(&keeper, &10);
+    assert_eq!(second_batch.len(), 1);
+    assert_ne!(
+        second_batch.get(0).unwrap().subscription_id,
+        first_batch.get(0).unwrap().subscription_id
+    );
+    assert_eq!(client.process_due(&keeper, &10).len(), 0);
+    assert_eq!(token_client.balance(&artist), 500);
+
+    // A payment settled directly is not charged again by the next batch
+    env.ledger().with_mut(|li| li.timestamp = MONTH_IN_SECONDS);
+    let monthly_id = String::from_str(&env, "3");
+    client.process_payment(&monthly_id);
+    let results = client.process_due(&keeper, &10);
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|r| r.subscription_id != monthly_id));
+    assert_eq!(token_client.balance(&artist), 800);
+}
+
+#[test]
+fn test_process_due_resumes_where_the_last_batch_stopped() {
+    let (env, client, subscriber, artist, token_client, token_admin) = setup_test();
+    let keeper = Address::generate(&env);
+
+    // Three subscriptions falling due on consecutive days
+    let mut ids = Vec::new(&env);
+    for day in 0..3 {
+        env.ledger()
+            .with_mut(|li| li.timestamp = day * DAY_IN_SECONDS);
+        let fan = if day == 0 {
+            subscriber.clone()
+        } else {
+            funded_subscriber(&env, &client, &token_client, &token_admin)
+        };
+        ids.push_back(client.create_subscription(
+            &fan,
+            &artist,
+            &token_client.address,
+            &100,
+            &SubscriptionFrequency::Weekly,
+        ));
+    }
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = WEEK_IN_SECONDS + 2 * DAY_IN_SECONDS);
+    for id in ids.iter() {
+        let batch = client.process_due(&keeper, &1);
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch.get(0).unwrap().subscription_id, id);
+    }
+    assert_eq!(client.process_due(&keeper, &10).len(), 0);
+    assert_eq!(token_client.balance(&artist), 600);
+}
+
+#[test]
+fn test_process_due_follows_billing_policy_changes() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+    let keeper = Address::generate(&env);
+    let sub_id = client.create_subscription(
+        &subscriber,
+        &artist,
+        &token_client.address,
+        &100,
+        &SubscriptionFrequency::Weekly,
+    );
+
+    // The payment fails and a retry is filed for the next day
+    token_client.approve(&subscriber, &client.address, &0, &100_000);
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    let results = client.process_due(&keeper, &10);
+    assert_eq!(results.get(0).unwrap().outcome, PaymentOutcome::PastDue);
+
+    // Without retries the subscription waits out a longer grace period instead
+    client.set_billing_policy(
+        &artist,
+        &BillingPolicy {
+            grace_period: 5 * DAY_IN_SECONDS,
+            max_retries: 0,
+            max_catch_up_periods: 0,
+        },
+    );
+    env.ledger()
+        .with_mut(|li| li.timestamp = WEEK_IN_SECONDS + DAY_IN_SECONDS);
+    assert_eq!(client.process_due(&keeper, &10).len(), 0);
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = WEEK_IN_SECONDS + 5 * DAY_IN_SECONDS);
+    let results = client.process_due(&keeper, &10);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results.get(0).unwrap().outcome, PaymentOutcome::Lapsed);
+    assert_eq!(
+        client.get_subscription(&sub_id).status,
+        SubscriptionStatus::Cancelled
+    );
+}
+
+#[test]
+fn test_keeper_paid_from_pool() {
+    let (env, client, subscriber, artist, token_client, token_admin) = setup_test();
+    let admin = Address::generate(&env);
+    let platform = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    token_admin.mint(&platform, &1_000);
+
+    assert_eq!(
+        client.try_fund_keeper_pool(&platform, &15),
+        Err(Ok(Error::KeeperNotConfigured))
+    );
+
+    client.initialize(&admin);
+    client.set_keeper_config(&KeeperConfig {
+        token: token_client.address.clone(),
+        tip_per_item: 10,
+    });
+    client.fund_keeper_pool(&platform, &15);
+
+    let second = funded_subscriber(&env, &client, &token_client, &token_admin);
+    for fan in [&subscriber, &second] {
+        client.create_subscription(
+            fan,
+            &artist,
+            &token_client.address,
+            &100,
+            &SubscriptionFrequency::Weekly,
+        );
+    }
+
+    // Batches run by keepers the admin has not approved settle but earn nothing
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    let outsider = Address::generate(&env);
+    assert_eq!(client.process_due(&outsider, &1).len(), 1);
+    assert_eq!(token_client.balance(&outsider), 0);
+    assert_eq!(client.get_keeper_pool(), 15);
+
+    // The remaining item earns 10, and the next one is capped by what is left in the pool
+    client.set_keeper(&keeper, &true);
+    assert!(client.is_keeper(&keeper));
+    assert_eq!(client.process_due(&keeper, &10).len(), 1);
+    assert_eq!(token_client.balance(&keeper), 10);
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = 2 * WEEK_IN_SECONDS);
+    assert_eq!(client.process_due(&keeper, &10).len(), 2);
+    assert_eq!(token_client.balance(&keeper), 15);
+    assert_eq!(client.get_keeper_pool(), 0);
+
+    client.set_keeper(&keeper, &false);
+    assert!(!client.is_keeper(&keeper));
+}
+
+#[test]
+fn test_keeper_tipped_only_for_collected_payments() {
+    let (env, client, subscriber, artist, token_client, token_admin) = setup_test();
+    let admin = Address::generate(&env);
+    let platform = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let lapsing = funded_subscriber(&env, &client, &token_client, &token_admin);
+    token_admin.mint(&platform, &1_000);
+    token_admin.mint(&artist, &1_000);
+    token_client.approve(&artist, &client.address, &1_000, &100_000);
+
+    client.initialize(&admin);
+    client.set_keeper_config(&KeeperConfig {
+        token: token_client.address.clone(),
+        tip_per_item: 10,
+    });
+    client.fund_keeper_pool(&platform, &1_000);
+    client.set_keeper(&keeper, &true);
+
+    // A small payment, an artist billing themselves and a payment that fails
+    for (payer, amount) in [(&subscriber, 30), (&artist, 100), (&lapsing, 100)] {
+        client.create_subscription(
+            payer,
+            &artist,
+            &token_client.address,
+            &amount,
+            &SubscriptionFrequency::Weekly,
+        );
+    }
+
+    token_client.approve(&lapsing, &client.address, &0, &100_000);
+
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    assert_eq!(client.process_due(&keeper, &10).len(), 3);
+
+    // Only the fan's payment earns a tip, capped at a tenth of the 30 collected
+    assert_eq!(token_client.balance(&keeper), 3);
+    assert_eq!(client.get_keeper_pool(), 997);
+}
+
+#[test]
+fn test_free_trial_converts_once_per_artist() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+    let mut terms = weekly_tier(&env, &token_client.address, 200, None);
+    terms.trial_periods = 1;
+    let tier_id = client.create_tier(&artist, &terms);
+
+    let sub_id = client.subscribe(&subscriber, &artist, &tier_id);
+    let sub = client.get_subscription(&sub_id);
+    assert_eq!(token_client.balance(&artist), 0);
+    assert_eq!(sub.periods_paid, 0);
+    assert_eq!(sub.next_payment_timestamp, WEEK_IN_SECONDS);
+    assert!(client.has_used_trial(&subscriber, &artist));
+
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    client.process_payment(&sub_id);
+    assert_eq!(token_client.balance(&artist), 200);
+    assert_eq!(client.get_subscription(&sub_id).periods_paid, 1);
+
+    // Resubscribing does not start another trial
+    client.cancel_subscription(&sub_id);
+    client.subscribe(&subscriber, &artist, &tier_id);
+    assert_eq!(token_client.balance(&artist), 400);
+}
+
+#[test]
+fn test_intro_price_then_regular_price() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+    let mut terms = weekly_tier(&env, &token_client.address, 200, None);
+    terms.intro_periods = 2;
+    terms.intro_price = 100;
+    let tier_id = client.create_tier(&artist, &terms);
+
+    let sub_id = client.subscribe(&subscriber, &artist, &tier_id);
+    assert_eq!(token_client.balance(&artist), 100);
+
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    client.process_payment(&sub_id);
+    assert_eq!(token_client.balance(&artist), 200);
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = 2 * WEEK_IN_SECONDS);
+    client.process_payment(&sub_id);
+    assert_eq!(token_client.balance(&artist), 400);
+
+    terms.intro_price = 200;
+    assert_eq!(
+        client.try_create_tier(&artist, &terms),
+        Err(Ok(Error::InvalidTier))
+    );
+}
+
+#[test]
+fn test_change_plan_prorates_upgrade_and_downgrade() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+    let sub_id = client.create_subscription(
+        &subscriber,
+        &artist,
+        &token_client.address,
+        &100,
+        &SubscriptionFrequency::Weekly,
+    );
+
+    // Half a week at 300 instead of 100 costs another 100 now
+    env.ledger()
+        .with_mut(|li| li.timestamp = WEEK_IN_SECONDS / 2);
+    assert_eq!(client.change_plan(&sub_id, &Plan::Amount(300), &None), 100);
+    assert_eq!(token_client.balance(&artist), 200);
+
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    client.process_payment(&sub_id);
+    assert_eq!(token_client.balance(&artist), 500);
+
+    // Going back down for the second half leaves 100 of credit, which covers the next payment
+    env.ledger()
+        .with_mut(|li| li.timestamp = WEEK_IN_SECONDS + WEEK_IN_SECONDS / 2);
+    assert_eq!(client.change_plan(&sub_id, &Plan::Amount(100), &None), -100);
+    assert_eq!(client.get_subscription(&sub_id).credit, 100);
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = 2 * WEEK_IN_SECONDS);
+    client.process_payment(&sub_id);
+    let sub = client.get_subscription(&sub_id);
+    assert_eq!(token_client.balance(&artist), 500);
+    assert_eq!(sub.credit, 0);
+    assert_eq!(sub.periods_paid, 3);
+    assert_eq!(client.payment_history(&sub_id).len(), 4);
+}
+
+#[test]
+fn test_change_plan_moves_between_tiers() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+    let basic = client.create_tier(
+        &artist,
+        &weekly_tier(&env, &token_client.address, 100, None),
+    );
+    let mut terms = weekly_tier(&env, &token_client.address, 200, None);
+    terms.frequency = SubscriptionFrequency::Monthly;
+    let premium = client.create_tier(&artist, &terms);
+
+    let sub_id = client.subscribe(&subscriber, &artist, &basic);
+    assert_eq!(
+        client.try_change_plan(
+            &sub_id,
+            &Plan::Tier(premium),
+            &Some(SubscriptionFrequency::Weekly)
+        ),
+        Err(Ok(Error::InvalidFrequency))
+    );
+
+    // A week of a 30-day plan at 200 costs 46, less the 100 already paid
+    assert_eq!(
+        client.change_plan(&sub_id, &Plan::Tier(premium), &None),
+        -54
+    );
+    let sub = client.get_subscription(&sub_id);
+    assert_eq!(sub.tier_id, Some(premium));
+    assert_eq!(sub.frequency, SubscriptionFrequency::Monthly);
+    assert_eq!(sub.next_payment_timestamp, WEEK_IN_SECONDS);
+    assert_eq!(client.get_tier(&artist, &basic).current_subscribers, 0);
+    assert_eq!(client.get_tier(&artist, &premium).current_subscribers, 1);
+
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    assert_eq!(
+        client.try_change_plan(&sub_id, &Plan::Tier(basic), &None),
+        Err(Ok(Error::PaymentDue))
+    );
+    client.process_payment(&sub_id);
+    assert_eq!(
+        client.get_subscription(&sub_id).next_payment_timestamp,
+        WEEK_IN_SECONDS + MONTH_IN_SECONDS
+    );
+}
+
+#[test]
+fn test_fixed_term_gift_expires() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+    let friend = Address::generate(&env);
+    let tier_id = client.create_tier(
+        &artist,
+        &weekly_tier(&env, &token_client.address, 100, None),
+    );
+
+    let sub_id = client.gift_subscription(&subscriber, &friend, &artist, &tier_id, &Some(2));
+    let sub = client.get_subscription(&sub_id);
+    assert_eq!(sub.payer, subscriber);
+    assert_eq!(sub.beneficiary, friend);
+    assert_eq!(token_client.balance(&subscriber), 9_900);
+
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    assert_eq!(client.process_payment(&sub_id), PaymentOutcome::Paid);
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = 2 * WEEK_IN_SECONDS);
+    assert_eq!(client.process_payment(&sub_id), PaymentOutcome::Expired);
+    assert_eq!(
+        client.get_subscription(&sub_id).status,
+        SubscriptionStatus::Expired
+    );
+    assert_eq!(token_client.balance(&artist), 200);
+    assert_eq!(client.get_tier(&artist, &tier_id).current_subscribers, 0);
+    assert_eq!(
+        client.try_gift_subscription(&subscriber, &friend, &artist, &tier_id, &Some(0)),
+        Err(Ok(Error::InvalidTerm))
+    );
+}
+
+#[test]
+fn test_transfer_keeps_billing_with_payer() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+    let friend = Address::generate(&env);
+    let other = Address::generate(&env);
+    let tier_id = client.create_tier(
+        &artist,
+        &weekly_tier(&env, &token_client.address, 100, None),
+    );
+
+    let sub_id = client.gift_subscription(&subscriber, &friend, &artist, &tier_id, &None);
+    client.transfer_subscription(&sub_id, &other);
+    assert_eq!(client.get_subscription(&sub_id).beneficiary, other);
+
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    client.process_payment(&sub_id);
+    assert_eq!(token_client.balance(&subscriber), 9_800);
+
+    client.cancel_subscription(&sub_id);
+    assert_eq!(
+        client.try_transfer_subscription(&sub_id, &friend),
+        Err(Ok(Error::InvalidStatus))
+    );
+}
+
+#[test]
+fn test_is_member_respects_rank_and_paid_through() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+    let basic = client.create_tier(
+        &artist,
+        &weekly_tier(&env, &token_client.address, 100, None),
+    );
+    let mut terms = weekly_tier(&env, &token_client.address, 300, None);
+    terms.rank = 2;
+    let premium = client.create_tier(&artist, &terms);
+
+    let sub_id = client.subscribe(&subscriber, &artist, &basic);
+    assert!(client.is_member(&subscriber, &artist, &basic));
+    assert!(!client.is_member(&subscriber, &artist, &premium));
+    let membership = client.membership_of(&subscriber, &artist).unwrap();
+    assert_eq!(membership.subscription_id, sub_id);
+    assert_eq!(membership.paid_through, WEEK_IN_SECONDS);
+
+    // Unpaid once the period runs out, until the renewal goes through
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    assert!(!client.is_member(&subscriber, &artist, &basic));
+    client.process_payment(&sub_id);
+    assert!(client.is_member(&subscriber, &artist, &basic));
+
+    client.change_plan(&sub_id, &Plan::Tier(premium), &None);
+    assert!(client.is_member(&subscriber, &artist, &premium));
+    assert_eq!(
+        client.try_is_member(&subscriber, &artist, &99),
+        Err(Ok(Error::TierNotFound))
+    );
+
+    // Cancelling keeps the membership for the rest of the paid period
+    client.cancel_subscription(&sub_id);
+    assert!(client.is_member(&subscriber, &artist, &premium));
+    let membership = client.membership_of(&subscriber, &artist).unwrap();
+    assert_eq!(membership.status, SubscriptionStatus::Cancelled);
+    assert_eq!(membership.paid_through, 2 * WEEK_IN_SECONDS);
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = 2 * WEEK_IN_SECONDS);
+    assert!(!client.is_member(&subscriber, &artist, &basic));
+    assert_eq!(client.membership_of(&subscriber, &artist), None);
+
+    // Subscribing again drops the ended subscription from the member index
+    let renewed = client.subscribe(&subscriber, &artist, &basic);
+    let ids = env.as_contract(&client.address, || {
+        storage::read_memberships(&env, &subscriber, &artist)
+    });
+    assert_eq!(ids, vec![&env, renewed]);
+}
+
+#[test]
+fn test_membership_follows_beneficiary() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+    let friend = Address::generate(&env);
+    let other = Address::generate(&env);
+    let tier_id = client.create_tier(
+        &artist,
+        &weekly_tier(&env, &token_client.address, 100, None),
+    );
+
+    let sub_id = client.gift_subscription(&subscriber, &friend, &artist, &tier_id, &None);
+    assert!(client.is_member(&friend, &artist, &tier_id));
+    assert!(!client.is_member(&subscriber, &artist, &tier_id));
+
+    client.transfer_subscription(&sub_id, &other);
+    assert!(!client.is_member(&friend, &artist, &tier_id));
+    assert!(client.is_member(&other, &artist, &tier_id));
+
+    // A cancelled gift still covers the period the payer already paid for
+    client.cancel_subscription(&sub_id);
+    assert!(client.is_member(&other, &artist, &tier_id));
+    env.ledger().with_mut(|li| li.timestamp = WEEK_IN_SECONDS);
+    assert_eq!(client.membership_of(&other, &artist), None);
+}
+
+#[test]
+fn test_artist_index_pages_and_active_count() {
+    let (env, client, subscriber, artist, token_client, token_admin) = setup_test();
+    let second = funded_subscriber(&env, &client, &token_client, &token_admin);
+    let third = funded_subscriber(&env, &client, &token_client, &token_admin);
+    let mut ids = Vec::new(&env);
+    for fan in [&subscriber, &second, &third] {
+        ids.push_back(client.create_subscription(
+            fan,
+            &artist,
+            &token_client.address,
+            &100,
+            &SubscriptionFrequency::Weekly,
+        ));
+    }
+    assert_eq!(client.active_subscriber_count(&artist), 3);
+
+    client.pause_subscription(&ids.get(0).unwrap());
+    client.cancel_subscription(&ids.get(2).unwrap());
+    assert_eq!(client.active_subscriber_count(&artist), 1);
+
+    let page = client.get_artist_subscriptions(&artist, &None, &0, &2);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(1).unwrap().id, ids.get(1).unwrap());
+    let page = client.get_artist_subscriptions(&artist, &None, &2, &2);
+    assert_eq!(page.len(), 1);
+
+    let cancelled =
+        client.get_artist_subscriptions(&artist, &Some(SubscriptionStatus::Cancelled), &0, &10);
+    assert_eq!(cancelled.len(), 1);
+    assert_eq!(cancelled.get(0).unwrap().id, ids.get(2).unwrap());
+    assert_eq!(
+        client.get_subscription_count(&SubscriptionIndex::Artist(artist.clone())),
+        3
+    );
+
+    client.resume_subscription(&ids.get(0).unwrap());
+    assert_eq!(client.active_subscriber_count(&artist), 2);
+}
+
+#[test]
+fn test_subscriber_index_follows_transfers() {
+    let (env, client, subscriber, artist, token_client, _) = setup_test();
+    let friend = Address::generate(&env);
+    let tier_id = client.create_tier(
+        &artist,
+        &weekly_tier(&env, &token_client.address, 100, None),
+    );
+
+    let gift = client.gift_subscription(&subscriber, &friend, &artist, &tier_id, &None);
+    let held = client.get_subscriber_subscriptions(&subscriber, &None, &0, &10);
+    let paid_for = client.get_payer_subscriptions(&subscriber, &None, &0, &10);
+    assert!(held.is_empty());
+    assert_eq!(paid_for.len(), 1);
+
+    // Handing the gift away and back lists it once, under its current holder only
+    client.transfer_subscription(&gift, &subscriber);
+    client.transfer_subscription(&gift, &friend);
+    let held = client.get_subscriber_subscriptions(&subscriber, &None, &0, &10);
+    assert!(held.is_empty());
+    let held = client.get_subscriber_subscriptions(&friend, &None, &0, &10);
+    assert_eq!(held.len(), 1);
+    assert_eq!(held.get(0).unwrap().id, gift);
+}
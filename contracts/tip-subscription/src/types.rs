@@ -1,10 +1,22 @@
-use soroban_sdk::{contracterror, contracttype, Address, String};
+use soroban_sdk::{contracterror, contracttype, Address, BytesN, String};
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum SubscriptionFrequency {
     Weekly,
-    Monthly,
+    Monthly, // Fixed 30 days
+    Daily,
+    Yearly,          // Fixed 365 days
+    Custom(u64),     // Interval in seconds, within the artist's bounds
+    CalendarMonthly, // Same day of every month as the subscription start
+}
+
+/// Shortest and longest billing interval an artist accepts
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IntervalBounds {
+    pub min_interval: u64,
+    pub max_interval: u64,
 }
 
 #[contracttype]
@@ -13,19 +25,131 @@ pub enum SubscriptionStatus {
     Active,
     Paused,
     Cancelled,
+    PastDue, // Last payment failed; retried until the grace period ends
+    Expired, // A fixed-term gift that has run its course
+}
+
+/// How long an artist's subscribers may stay past due, how often payment is retried,
+/// and how many missed periods a late payment may catch up
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BillingPolicy {
+    pub grace_period: u64,
+    pub max_retries: u32,
+    pub max_catch_up_periods: u32, // Missed periods charged on top of the current one
+}
+
+/// Result of a `process_payment` call that changed the subscription
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PaymentOutcome {
+    Paid,
+    PastDue, // The pull failed and will be retried
+    Lapsed,  // The grace period ran out and the subscription was cancelled
+    Expired, // A fixed-term gift ended instead of renewing
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Subscription {
     pub id: String,
-    pub subscriber: Address,
+    pub payer: Address,       // Pays, and controls billing and cancellation
+    pub beneficiary: Address, // Holds the membership; the payer too unless it is a gift
     pub artist: Address,
     pub token: Address,
     pub amount: i128,
     pub frequency: SubscriptionFrequency,
     pub status: SubscriptionStatus,
     pub next_payment_timestamp: u64,
+    pub tier_id: Option<u32>, // None for free-form amounts
+    pub failed_attempts: u32, // Failed pulls for the payment currently due
+    pub started_at: u64,      // Cycles are anchored here; moves when the frequency changes
+    pub periods_paid: u32,    // Free trial periods are not counted
+    pub last_paid_at: u64,
+    pub trial_periods_left: u32,
+    pub intro_periods_left: u32,
+    pub intro_price: i128,
+    pub period_price: i128, // What the current period cost; prorated on plan changes
+    pub credit: i128,       // Taken off upcoming payments after a downgrade
+    pub periods_left: Option<u32>, // Periods still to bill on a fixed-term gift
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PaymentRecord {
+    pub amount: i128,
+    pub periods: u32, // More than one when missed periods were caught up
+    pub paid_at: u64,
+    pub paid_through: u64,
+}
+
+/// The plan `change_plan` moves a subscription to
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Plan {
+    Amount(i128), // Free-form amount, billed at the given or current frequency
+    Tier(u32),    // One of the artist's tiers, billed at the tier's frequency
+}
+
+/// Secondary indexes over subscriptions, each an append-only list of subscription ids
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SubscriptionIndex {
+    Artist(Address),
+    Payer(Address),
+    Beneficiary(Address), // Transfers add an entry for the new beneficiary
+}
+
+/// A member's standing with an artist, from their best current subscription
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Membership {
+    pub subscription_id: String,
+    pub tier_id: Option<u32>, // None for free-form amounts
+    pub rank: u32,            // The tier's rank; 0 without a tier
+    pub status: SubscriptionStatus,
+    pub paid_through: u64,
+}
+
+/// What `process_due` did with one subscription
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DueResult {
+    pub subscription_id: String,
+    pub outcome: PaymentOutcome,
+}
+
+/// Reward paid from the platform-funded pool to whoever runs `process_due`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeeperConfig {
+    pub token: Address,
+    pub tip_per_item: i128, // Paid for each payment the batch collects
+}
+
+/// Pricing and limits of an artist's subscription tier, mirroring the backend `SubscriptionTier`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TierTerms {
+    pub token: Address,
+    pub price: i128, // Charged once per `frequency` period
+    pub frequency: SubscriptionFrequency,
+    pub max_subscribers: Option<u32>, // None for no cap
+    pub perks_hash: BytesN<32>,       // Hash of the off-chain perks description
+    pub rank: u32,                    // Higher ranks unlock everything lower ones do
+    pub trial_periods: u32,           // Free periods before the first charge; 0 for none
+    pub intro_periods: u32,           // Periods charged at `intro_price` after the trial
+    pub intro_price: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tier {
+    pub tier_id: u32,
+    pub artist: Address,
+    pub terms: TierTerms,
+    pub current_subscribers: u32,
+    pub is_active: bool,
 }
 
 #[contracterror]
@@ -36,4 +160,18 @@ pub enum Error {
     InvalidStatus = 3,
     PaymentTooEarly = 4,
     InvalidAmount = 5,
+    TierNotFound = 6,
+    TierInactive = 7,
+    TierFull = 8,
+    InvalidTier = 9,
+    PaymentFailed = 10,
+    RetriesExhausted = 11,
+    AlreadyInitialized = 12,
+    NotInitialized = 13,
+    KeeperNotConfigured = 14,
+    KeeperPoolFunded = 15,
+    InvalidFrequency = 16,
+    PaymentDue = 17,
+    InvalidTerm = 18,
+    InvalidBillingPolicy = 19,
 }
\ No newline at end of file